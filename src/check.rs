@@ -0,0 +1,292 @@
+use std::fmt::{Display, Formatter};
+use std::net::ToSocketAddrs;
+
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::Certificate;
+
+use crate::http::{HttpClient, HttpConnectionProfile};
+use crate::ini::{IniProfile, IniProfileStore};
+use crate::url::Endpoint;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Display for CheckStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckStatus::Pass => write!(f, "PASS"),
+            CheckStatus::Warn => write!(f, "WARN"),
+            CheckStatus::Fail => write!(f, "FAIL"),
+        }
+    }
+}
+
+/// Outcome of a single validation of `--check-profile`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+impl Display for CheckResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}: {}", self.status, self.name, self.detail)
+    }
+}
+
+/// Loads the profile `name` and validates it without sending any
+/// request. Only DNS is queried to tell whether the hosts resolve.
+pub fn check_profile(store: &IniProfileStore, name: &str) -> Vec<CheckResult> {
+    let profile = match store.get_profile(name) {
+        Ok(Some(profile)) => profile,
+        Ok(None) => {
+            return vec![CheckResult::new(
+                "profile",
+                CheckStatus::Fail,
+                format!("profile '{name}' not found"),
+            )]
+        }
+        Err(e) => {
+            return vec![CheckResult::new(
+                "profile",
+                CheckStatus::Fail,
+                format!("{e:#}"),
+            )]
+        }
+    };
+
+    let mut results = vec![CheckResult::new(
+        "profile",
+        CheckStatus::Pass,
+        format!("profile '{name}' loaded"),
+    )];
+    results.extend(check_loaded_profile(&profile));
+    results
+}
+
+fn check_loaded_profile(profile: &IniProfile) -> Vec<CheckResult> {
+    let mut results = vec![];
+
+    match profile.server() {
+        Some(server) => results.push(check_endpoint("host", server)),
+        None => results.push(CheckResult::new(
+            "host",
+            CheckStatus::Warn,
+            "not set, only absolute URLs can be used",
+        )),
+    }
+
+    if let Some(proxy) = profile.proxy() {
+        results.push(check_endpoint("proxy", proxy));
+    }
+
+    if profile.user().is_some() && profile.password().is_none() {
+        results.push(CheckResult::new(
+            "password",
+            CheckStatus::Warn,
+            "user is set without a password",
+        ));
+    }
+
+    if let Some(ca_cert) = profile.ca_cert() {
+        results.push(check_ca_cert(ca_cert));
+    }
+
+    if profile.insecure() == Some(true) {
+        results.push(CheckResult::new(
+            "insecure",
+            CheckStatus::Warn,
+            "TLS certificate verification is disabled",
+        ));
+    }
+
+    let headers = profile.headers();
+    let mut names = headers.keys().collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        let value = &headers[name];
+        let status = if HeaderName::from_bytes(name.as_bytes()).is_err() {
+            CheckResult::new(
+                "header",
+                CheckStatus::Fail,
+                format!("invalid name '{name}'"),
+            )
+        } else if HeaderValue::from_str(value).is_err() {
+            CheckResult::new(
+                "header",
+                CheckStatus::Fail,
+                format!("invalid value for '{name}'"),
+            )
+        } else {
+            CheckResult::new("header", CheckStatus::Pass, name.to_string())
+        };
+        results.push(status);
+    }
+
+    // Everything above is also validated when building the client,
+    // this catches the combinations we don't check individually.
+    results.push(match HttpClient::build_client(profile) {
+        Ok(_) => CheckResult::new("client", CheckStatus::Pass, "HTTP client can be built"),
+        Err(e) => CheckResult::new("client", CheckStatus::Fail, format!("{e:#}")),
+    });
+
+    results
+}
+
+fn check_endpoint(name: &str, endpoint: &Endpoint) -> CheckResult {
+    let port = match (endpoint.port(), endpoint.scheme().map(|s| s.as_str())) {
+        (Some(port), _) => port,
+        (None, Some("https")) => 443,
+        (None, Some("http")) | (None, None) => 80,
+        (None, Some(scheme)) => {
+            return CheckResult::new(
+                name,
+                CheckStatus::Fail,
+                format!("unsupported scheme '{scheme}' in {endpoint}"),
+            )
+        }
+    };
+
+    let resolved = (endpoint.host().as_str(), port)
+        .to_socket_addrs()
+        .is_ok_and(|mut addrs| addrs.next().is_some());
+    match resolved {
+        true => CheckResult::new(name, CheckStatus::Pass, format!("{endpoint} resolves")),
+        false => CheckResult::new(
+            name,
+            CheckStatus::Fail,
+            format!("{endpoint} does not resolve"),
+        ),
+    }
+}
+
+fn check_ca_cert(ca_cert: &str) -> CheckResult {
+    let path = shellexpand::tilde(ca_cert).to_string();
+    match std::fs::read(&path) {
+        Err(e) => CheckResult::new("ca_cert", CheckStatus::Fail, format!("'{path}': {e}")),
+        Ok(data) => match Certificate::from_pem_bundle(&data) {
+            Ok(certs) if !certs.is_empty() => CheckResult::new("ca_cert", CheckStatus::Pass, path),
+            _ => CheckResult::new(
+                "ca_cert",
+                CheckStatus::Fail,
+                format!("'{path}' is not a PEM certificate"),
+            ),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn store_with(content: &str) -> (NamedTempFile, IniProfileStore) {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        let store = IniProfileStore::new(file.path().to_str().unwrap());
+        (file, store)
+    }
+
+    fn status_of<'a>(results: &'a [CheckResult], name: &str) -> Vec<&'a CheckStatus> {
+        results
+            .iter()
+            .filter(|r| r.name == name)
+            .map(|r| &r.status)
+            .collect()
+    }
+
+    #[test]
+    fn test_check_healthy_profile() {
+        let (_file, store) = store_with(
+            "[default]\n\
+             host=http://localhost:8080\n\
+             user=admin\n\
+             password=secret\n\
+             @content-type=application/json\n",
+        );
+
+        let results = check_profile(&store, "default");
+
+        assert!(
+            results.iter().all(|r| r.status == CheckStatus::Pass),
+            "{results:?}"
+        );
+        assert_eq!(status_of(&results, "header"), vec![&CheckStatus::Pass]);
+        assert_eq!(
+            results[1].to_string(),
+            "[PASS] host: http://localhost:8080 resolves"
+        );
+    }
+
+    #[test]
+    fn test_check_reports_problems() {
+        let (_file, store) = store_with(
+            "[broken]\n\
+             host=https://localhost\n\
+             user=admin\n\
+             ca_cert=/nonexistent/ca.pem\n\
+             insecure=true\n\
+             @bad header=value\n",
+        );
+
+        let results = check_profile(&store, "broken");
+
+        assert_eq!(status_of(&results, "host"), vec![&CheckStatus::Pass]);
+        assert_eq!(status_of(&results, "password"), vec![&CheckStatus::Warn]);
+        assert_eq!(status_of(&results, "ca_cert"), vec![&CheckStatus::Fail]);
+        assert_eq!(status_of(&results, "insecure"), vec![&CheckStatus::Warn]);
+        assert_eq!(status_of(&results, "header"), vec![&CheckStatus::Fail]);
+        assert_eq!(status_of(&results, "client"), vec![&CheckStatus::Fail]);
+    }
+
+    #[test]
+    fn test_check_invalid_ca_cert() {
+        let mut cert = NamedTempFile::new().unwrap();
+        cert.write_all(b"not a certificate").unwrap();
+
+        let result = check_ca_cert(cert.path().to_str().unwrap());
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.detail.ends_with("is not a PEM certificate"));
+    }
+
+    #[test]
+    fn test_check_missing_and_unparsable_profile() {
+        let (_file, store) = store_with("[default]\nmax_time=soon\n");
+
+        let results = check_profile(&store, "nonexistent");
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].to_string(),
+            "[FAIL] profile: profile 'nonexistent' not found"
+        );
+
+        let results = check_profile(&store, "default");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].status, CheckStatus::Fail);
+        assert!(results[0].detail.contains("max_time"));
+    }
+
+    #[test]
+    fn test_check_endpoint_scheme() {
+        let endpoint = Endpoint::parse("ftp://localhost").unwrap();
+        let result = check_endpoint("host", &endpoint);
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert_eq!(result.detail, "unsupported scheme 'ftp' in ftp://localhost");
+    }
+}
@@ -0,0 +1,333 @@
+use std::fmt::Display;
+
+use crate::http::{ConnectionPolicy, HttpConnectionProfile, DEFAULT_MAX_REDIRECTS};
+use crate::utils::{is_secret_header, REDACTED};
+
+const NONE: &str = "<none>";
+
+// Tells whether a policy sets one of its fields
+type PolicyField = fn(&ConnectionPolicy) -> bool;
+
+/// Where a setting of the effective configuration comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    CommandLine,
+    Profile,
+    /// The `.netrc` credentials, looked up once everything else is merged
+    Netrc,
+    Default,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let source = match self {
+            Source::CommandLine => "command line",
+            Source::Profile => "profile",
+            Source::Netrc => ".netrc",
+            Source::Default => "default",
+        };
+        write!(f, "{source}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: String,
+    pub source: Source,
+}
+
+/// The settings of `merged`, the profile once the command line `cmd`
+/// has been merged into `profile`, with where each of them comes from.
+/// Passwords and credential headers are redacted.
+pub fn effective_config(
+    merged: &impl HttpConnectionProfile,
+    profile: &impl HttpConnectionProfile,
+    cmd: &impl HttpConnectionProfile,
+) -> Vec<ConfigEntry> {
+    let source = |in_cmd: bool, in_profile: bool, in_merged: bool| match () {
+        _ if in_cmd => Source::CommandLine,
+        _ if in_profile => Source::Profile,
+        _ if in_merged => Source::Netrc,
+        _ => Source::Default,
+    };
+    let mut entries = vec![];
+    let mut push = |key: &str, value: Option<String>, default: &str, source: Source| {
+        entries.push(ConfigEntry {
+            key: key.to_string(),
+            value: value.unwrap_or_else(|| default.to_string()),
+            source,
+        })
+    };
+
+    push(
+        "server",
+        merged.server().map(|s| s.to_string()),
+        NONE,
+        source(cmd.server().is_some(), profile.server().is_some(), false),
+    );
+    // The password comes along with the user
+    let user_source = source(
+        cmd.user().is_some(),
+        profile.user().is_some(),
+        merged.user().is_some(),
+    );
+    push("user", merged.user().cloned(), NONE, user_source);
+    push(
+        "password",
+        merged.password().map(|_| REDACTED.to_string()),
+        NONE,
+        user_source,
+    );
+    push(
+        "insecure",
+        merged.insecure().map(|v| v.to_string()),
+        "false",
+        source(
+            cmd.insecure().is_some(),
+            profile.insecure().is_some(),
+            false,
+        ),
+    );
+    push(
+        "ca-cert",
+        merged.ca_cert().cloned(),
+        NONE,
+        source(cmd.ca_cert().is_some(), profile.ca_cert().is_some(), false),
+    );
+    push(
+        "proxy",
+        merged.proxy().map(|p| p.to_string()),
+        NONE,
+        source(cmd.proxy().is_some(), profile.proxy().is_some(), false),
+    );
+    let proxy_user_source = source(
+        cmd.proxy_user().is_some(),
+        profile.proxy_user().is_some(),
+        false,
+    );
+    push(
+        "proxy-user",
+        merged.proxy_user().cloned(),
+        NONE,
+        proxy_user_source,
+    );
+    push(
+        "proxy-password",
+        merged.proxy_password().map(|_| REDACTED.to_string()),
+        NONE,
+        proxy_user_source,
+    );
+
+    let (policy, cmd_policy, profile_policy) = (merged.policy(), cmd.policy(), profile.policy());
+    let policy_source =
+        |field: PolicyField| source(field(&cmd_policy), field(&profile_policy), false);
+    push(
+        "connect-timeout",
+        policy.connect_timeout.map(|d| format!("{d:?}")),
+        NONE,
+        policy_source(|p| p.connect_timeout.is_some()),
+    );
+    push(
+        "max-time",
+        policy.max_time.map(|d| format!("{d:?}")),
+        NONE,
+        policy_source(|p| p.max_time.is_some()),
+    );
+    push(
+        "retries",
+        policy.retries.map(|r| r.to_string()),
+        "0",
+        policy_source(|p| p.retries.is_some()),
+    );
+    push(
+        "retry-all-methods",
+        policy.retry_all_methods.map(|r| r.to_string()),
+        "false",
+        policy_source(|p| p.retry_all_methods.is_some()),
+    );
+    push(
+        "follow",
+        policy.follow.map(|f| f.to_string()),
+        "true",
+        policy_source(|p| p.follow.is_some()),
+    );
+    push(
+        "max-redirects",
+        policy.max_redirects.map(|m| m.to_string()),
+        &DEFAULT_MAX_REDIRECTS.to_string(),
+        policy_source(|p| p.max_redirects.is_some()),
+    );
+    push(
+        "max-header-size",
+        policy.max_header_size.map(|m| m.to_string()),
+        NONE,
+        policy_source(|p| p.max_header_size.is_some()),
+    );
+    push(
+        "location-trusted",
+        policy.location_trusted.map(|l| l.to_string()),
+        "false",
+        policy_source(|p| p.location_trusted.is_some()),
+    );
+    push(
+        "abort-on-redirect-loop",
+        policy.abort_on_redirect_loop.map(|a| a.to_string()),
+        "false",
+        policy_source(|p| p.abort_on_redirect_loop.is_some()),
+    );
+
+    for entry in merged.resolve() {
+        let addrs = entry
+            .addrs
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>();
+        push(
+            &format!("resolve {}:{}", entry.host, entry.port),
+            Some(addrs.join(",")),
+            NONE,
+            Source::CommandLine,
+        );
+    }
+
+    if !merged.dns_servers().is_empty() {
+        let servers = merged
+            .dns_servers()
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        push(
+            "dns-server",
+            Some(servers.join(",")),
+            NONE,
+            Source::CommandLine,
+        );
+    }
+
+    let headers = merged.headers();
+    let mut names = headers.keys().collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        let value = match is_secret_header(name) {
+            true => REDACTED.to_string(),
+            false => headers[name].clone(),
+        };
+        push(
+            &format!("header {name}"),
+            Some(value),
+            NONE,
+            source(
+                cmd.headers().contains_key(name),
+                profile.headers().contains_key(name),
+                false,
+            ),
+        );
+    }
+
+    entries
+}
+
+/// Formats the entries as aligned `key  value  (source)` lines.
+pub fn format_config(entries: &[ConfigEntry]) -> String {
+    let key_width = entries.iter().map(|e| e.key.len()).max().unwrap_or(0);
+    let value_width = entries.iter().map(|e| e.value.len()).max().unwrap_or(0);
+    entries
+        .iter()
+        .map(|e| {
+            format!(
+                "{:key_width$}  {:value_width$}  ({})\n",
+                e.key, e.value, e.source
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::CommandLineArgs;
+    use crate::ini::get_blank_profile;
+
+    fn find<'a>(entries: &'a [ConfigEntry], key: &str) -> &'a ConfigEntry {
+        entries.iter().find(|e| e.key == key).unwrap()
+    }
+
+    #[test]
+    fn test_effective_config() {
+        let mut profile = get_blank_profile();
+        profile.set_credentials("admin", Some("secret"));
+        profile.merge_profile(&CommandLineArgs::parse_from([
+            "http",
+            "GET",
+            "https://api.example.com/",
+            "--insecure",
+            "-H",
+            "Authorization: Bearer abc",
+            "-H",
+            "X-Team: a",
+        ]));
+        let cmd = CommandLineArgs::parse_from([
+            "http",
+            "GET",
+            "/items",
+            "--max-time",
+            "5",
+            "-H",
+            "X-Team: b",
+        ]);
+        let mut merged = profile.clone();
+        merged.merge_profile(&cmd);
+
+        let entries = effective_config(&merged, &profile, &cmd);
+        let entry = |key| {
+            let e = find(&entries, key);
+            (e.value.as_str(), e.source)
+        };
+        assert_eq!(
+            entry("server"),
+            ("https://api.example.com", Source::Profile)
+        );
+        assert_eq!(entry("user"), ("admin", Source::Profile));
+        assert_eq!(entry("password"), (REDACTED, Source::Profile));
+        assert_eq!(entry("insecure"), ("true", Source::Profile));
+        assert_eq!(entry("max-time"), ("5s", Source::CommandLine));
+        assert_eq!(entry("retries"), ("0", Source::Default));
+        assert_eq!(entry("max-redirects"), ("10", Source::Default));
+        assert_eq!(entry("proxy"), (NONE, Source::Default));
+        assert_eq!(entry("header authorization"), (REDACTED, Source::Profile));
+        assert_eq!(entry("header x-team"), ("b", Source::CommandLine));
+    }
+
+    #[test]
+    fn test_netrc_credentials() {
+        let profile = get_blank_profile();
+        let mut merged = profile.clone();
+        merged.set_credentials("alice", None);
+
+        let cmd = CommandLineArgs::parse_from(["http", "GET", "/"]);
+        let entries = effective_config(&merged, &profile, &cmd);
+        assert_eq!(find(&entries, "user").source, Source::Netrc);
+        assert_eq!(find(&entries, "password").value, NONE);
+    }
+
+    #[test]
+    fn test_format_config() {
+        let entries = vec![
+            ConfigEntry {
+                key: "user".to_string(),
+                value: "admin".to_string(),
+                source: Source::Profile,
+            },
+            ConfigEntry {
+                key: "max-time".to_string(),
+                value: "5s".to_string(),
+                source: Source::CommandLine,
+            },
+        ];
+        assert_eq!(
+            format_config(&entries),
+            "user      admin  (profile)\nmax-time  5s     (command line)\n"
+        );
+    }
+}
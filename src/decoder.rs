@@ -1,16 +1,34 @@
 use crate::utils::Result;
+use anyhow::anyhow;
 use bytes::Bytes;
-use encoding_rs::SHIFT_JIS;
+use encoding_rs::{Encoding, BIG5, EUC_JP, EUC_KR, GBK, SHIFT_JIS, UTF_8, WINDOWS_1252};
 use flate2::read::DeflateDecoder;
 use flate2::read::GzDecoder;
-use std::io::Read;
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use std::io::{Read, Write};
+use std::ops::RangeInclusive;
 use std::str;
 
 pub const ENC_NONE: &str = ":plaintext:";
+pub const ENC_IDENTITY: &str = "identity";
 pub const ENC_GZIP: &str = "gzip";
 pub const ENC_DEFLATE: &str = "deflate";
 pub const ENC_ZSTD: &str = "zstd";
 
+/// The content-encodings we can decompress, in order of preference.
+/// They are the ones advertised in the default `Accept-Encoding`
+/// header, so keep both in line when adding one.
+pub const SUPPORTED_ENCODINGS: [&str; 3] = [ENC_GZIP, ENC_DEFLATE, ENC_ZSTD];
+
+const FLATE2_LEVELS: RangeInclusive<u32> = 0..=9;
+const ZSTD_LEVELS: RangeInclusive<u32> = 1..=22;
+const BINARY_SAMPLE_SIZE: usize = 8192;
+
+// The legacy multi-byte charsets tried in turn by `decode_text_auto`
+// when the body is not UTF-8. The first one the body is valid in wins.
+const SNIFFED_CHARSETS: [&Encoding; 5] = [SHIFT_JIS, EUC_JP, GBK, EUC_KR, BIG5];
+
 pub fn decode_gzip(data: &[u8]) -> Result<Bytes> {
     let mut decoder = GzDecoder::new(data);
     let mut decoded_data = Vec::new();
@@ -33,14 +51,154 @@ pub fn decode_zstd(data: &[u8]) -> Result<Bytes> {
     Ok(Bytes::copy_from_slice(&decoded_data))
 }
 
+/// Returns the range of compression levels accepted by the given encoding.
+pub fn compression_levels(encoding: &str) -> Result<RangeInclusive<u32>> {
+    match encoding {
+        ENC_GZIP | ENC_DEFLATE => Ok(FLATE2_LEVELS),
+        ENC_ZSTD => Ok(ZSTD_LEVELS),
+        _ => Err(anyhow!("Unsupported compression '{encoding}'")),
+    }
+}
+
+fn validate_level(encoding: &str, level: u32) -> Result<()> {
+    let levels = compression_levels(encoding)?;
+    if !levels.contains(&level) {
+        return Err(anyhow!(
+            "Invalid {encoding} compression level {level}. Expected {}-{}",
+            levels.start(),
+            levels.end()
+        ));
+    }
+    Ok(())
+}
+
+pub fn encode_gzip(data: &[u8], level: Option<u32>) -> Result<Bytes> {
+    let level = level.map(Compression::new).unwrap_or_default();
+    let mut encoder = GzEncoder::new(Vec::new(), level);
+    encoder.write_all(data)?;
+    Ok(Bytes::from(encoder.finish()?))
+}
+
+pub fn encode_deflate(data: &[u8], level: Option<u32>) -> Result<Bytes> {
+    let level = level.map(Compression::new).unwrap_or_default();
+    let mut encoder = DeflateEncoder::new(Vec::new(), level);
+    encoder.write_all(data)?;
+    Ok(Bytes::from(encoder.finish()?))
+}
+
+pub fn encode_zstd(data: &[u8], level: Option<u32>) -> Result<Bytes> {
+    // zstd treats level 0 as "use the library default"
+    let level = level.map(|l| l as i32).unwrap_or(0);
+    Ok(Bytes::from(zstd::encode_all(data, level)?))
+}
+
+pub fn encode_bytes(data: &[u8], encoding: &str, level: Option<u32>) -> Result<Bytes> {
+    if let Some(level) = level {
+        validate_level(encoding, level)?;
+    }
+
+    match encoding {
+        ENC_GZIP => encode_gzip(data, level),
+        ENC_DEFLATE => encode_deflate(data, level),
+        ENC_ZSTD => encode_zstd(data, level),
+        _ => Err(anyhow!("Unsupported compression '{encoding}'")),
+    }
+}
+
+/// Whether `data` looks like binary content (an image, an archive, ...)
+/// rather than text: it has NUL bytes, or more than 10% of control
+/// characters other than whitespace. Only the beginning is looked at.
+pub fn is_binary(data: &[u8]) -> bool {
+    let sample = &data[..data.len().min(BINARY_SAMPLE_SIZE)];
+    if sample.contains(&0) {
+        return true;
+    }
+    let controls = sample
+        .iter()
+        .filter(|&&b| (b < 0x20 && !b"\t\n\r\x0c\x1b".contains(&b)) || b == 0x7f)
+        .count();
+    controls * 10 > sample.len()
+}
+
+/// Decodes the (decompressed) body as text, or None if it is binary or
+/// neither UTF-8 nor SHIFT_JIS.
+pub fn decode_text(data: &[u8]) -> Option<String> {
+    if is_binary(data) {
+        return None;
+    }
+    decode_bytes(data, ENC_NONE).ok()
+}
+
+/// A body decoded as text, with the charset it was decoded with
+pub type CharsetText = (String, &'static Encoding);
+
+/// Decodes the (decompressed) body as text like `decode_text`, but with
+/// the charset of its byte order mark, else the `declared` one (from the
+/// Content-Type), else UTF-8 or the first legacy charset the body is
+/// valid in, else windows-1252. It is a cheap guess, not a statistical
+/// detection. Returns the text and the charset used, or None if binary.
+pub fn decode_text_auto(data: &[u8], declared: Option<&str>) -> Option<CharsetText> {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(data) {
+        let (text, _) = encoding.decode_without_bom_handling(&data[bom_length..]);
+        return Some((text.to_string(), encoding));
+    }
+    if is_binary(data) {
+        return None;
+    }
+    let declared = declared.and_then(|label| Encoding::for_label(label.trim().as_bytes()));
+    declared
+        .into_iter()
+        .chain([UTF_8])
+        .chain(SNIFFED_CHARSETS)
+        .find_map(|encoding| {
+            encoding
+                .decode_without_bom_handling_and_without_replacement(data)
+                .map(|text| (text.to_string(), encoding))
+        })
+        .or_else(|| {
+            let (text, _) = WINDOWS_1252.decode_without_bom_handling(data);
+            Some((text.to_string(), WINDOWS_1252))
+        })
+}
+
+/// The `charset` parameter of a Content-Type value, if any.
+pub fn charset_param(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// The value of the `Accept-Encoding` header sent by default.
+pub fn accept_encoding() -> String {
+    SUPPORTED_ENCODINGS.join(", ")
+}
+
+type Decoder = fn(&[u8]) -> Result<Bytes>;
+
+/// Returns the decompressor of `encoding`, or None for a body that is
+/// not compressed (or compressed with an encoding we do not support).
+fn decoder_for(encoding: &str) -> Option<Decoder> {
+    match encoding {
+        ENC_GZIP => Some(decode_gzip),
+        ENC_DEFLATE => Some(decode_deflate),
+        ENC_ZSTD => Some(decode_zstd),
+        _ => None,
+    }
+}
+
+pub fn decompress_bytes(data: &[u8], encoding: &str) -> Result<Bytes> {
+    match decoder_for(encoding) {
+        Some(decode) => decode(data),
+        None => Ok(Bytes::copy_from_slice(data)),
+    }
+}
+
 pub fn decode_bytes(data: &[u8], encoding: &str) -> Result<String> {
     // Decompress the body bytes based on the encoding
-    let body_bytes = match encoding {
-        ENC_GZIP => decode_gzip(data),
-        ENC_DEFLATE => decode_deflate(data),
-        ENC_ZSTD => decode_zstd(data),
-        _ => Ok(Bytes::copy_from_slice(data)),
-    }?;
+    let body_bytes = decompress_bytes(data, encoding)?;
 
     // Try decoding the body as UTF-8 first, and if it fails,
     // fall back to SHIFT_JIS
@@ -61,6 +219,18 @@ pub fn decode_bytes(data: &[u8], encoding: &str) -> Result<String> {
     Ok(body)
 }
 
+/// Decompresses the body and decodes it with the given charset label
+/// (e.g. `iso-8859-1`, `euc-jp`) instead of the UTF-8/SHIFT_JIS guess.
+pub fn decode_bytes_with_charset(data: &[u8], encoding: &str, charset: &str) -> Result<String> {
+    let body_bytes = decompress_bytes(data, encoding)?;
+    let decoder = Encoding::for_label(charset.trim().as_bytes())
+        .ok_or_else(|| anyhow!("Unknown charset '{charset}'"))?;
+    decoder
+        .decode_without_bom_handling_and_without_replacement(&body_bytes)
+        .map(|s| s.to_string())
+        .ok_or_else(|| anyhow!("Failed to decode body as {}", decoder.name()))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -252,6 +422,85 @@ mod test {
         }
     }
 
+    #[test]
+    fn encode_bytes_should_roundtrip_with_each_encoding() {
+        let original_data = "Hello, World! ".repeat(100);
+
+        for encoding in SUPPORTED_ENCODINGS {
+            let compressed = encode_bytes(original_data.as_bytes(), encoding, None).unwrap();
+            assert!(compressed.len() < original_data.len());
+            let result = decode_bytes(&compressed, encoding).unwrap();
+            assert_eq!(result, original_data, "Failed for encoding: {encoding}");
+        }
+    }
+
+    #[test]
+    fn encode_bytes_should_accept_levels_within_range() {
+        let data = "level test".as_bytes();
+
+        for (encoding, level) in [
+            (ENC_GZIP, 0),
+            (ENC_GZIP, 9),
+            (ENC_DEFLATE, 1),
+            (ENC_ZSTD, 1),
+            (ENC_ZSTD, 22),
+        ] {
+            let compressed = encode_bytes(data, encoding, Some(level)).unwrap();
+            assert_eq!(decode_bytes(&compressed, encoding).unwrap(), "level test");
+        }
+    }
+
+    #[test]
+    fn encode_bytes_should_reject_levels_out_of_range() {
+        let data = "level test".as_bytes();
+
+        for (encoding, level) in [
+            (ENC_GZIP, 10),
+            (ENC_DEFLATE, 42),
+            (ENC_ZSTD, 0),
+            (ENC_ZSTD, 23),
+        ] {
+            let err = encode_bytes(data, encoding, Some(level)).unwrap_err();
+            assert!(
+                err.to_string().contains("Invalid"),
+                "Expected error for {encoding} level {level}"
+            );
+        }
+    }
+
+    #[test]
+    fn encode_bytes_should_reject_unknown_encoding() {
+        assert!(encode_bytes(b"data", "brotli", None).is_err());
+    }
+
+    #[test]
+    fn decode_bytes_with_charset_should_use_given_charset() {
+        // "café" in ISO-8859-1
+        let data = [0x63, 0x61, 0x66, 0xE9];
+        let result = decode_bytes_with_charset(&data, ENC_NONE, "iso-8859-1").unwrap();
+        assert_eq!(result, "café");
+    }
+
+    #[test]
+    fn decode_bytes_with_charset_should_decompress_first() {
+        let compressed = encode_gzip("テスト".as_bytes(), None).unwrap();
+        let result = decode_bytes_with_charset(&compressed, ENC_GZIP, "UTF-8").unwrap();
+        assert_eq!(result, "テスト");
+    }
+
+    #[test]
+    fn decode_bytes_with_charset_should_fail_on_mismatch() {
+        let data = [0xFF, 0xFE, 0xFD];
+        let err = decode_bytes_with_charset(&data, ENC_NONE, "utf-8").unwrap_err();
+        assert!(err.to_string().contains("Failed to decode body as UTF-8"));
+    }
+
+    #[test]
+    fn decode_bytes_with_charset_should_reject_unknown_charset() {
+        let err = decode_bytes_with_charset(b"data", ENC_NONE, "klingon").unwrap_err();
+        assert!(err.to_string().contains("Unknown charset"));
+    }
+
     #[test]
     fn test_decode_bytes_integration() {
         use flate2::write::GzEncoder;
@@ -265,4 +514,71 @@ mod test {
         let result = decode_bytes(&compressed, ENC_GZIP).unwrap();
         assert_eq!(result, original_data);
     }
+
+    #[test]
+    fn test_is_binary() {
+        assert!(is_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(is_binary(&[0x01, 0x02, 0x03, b'a', b'b']));
+        assert!(!is_binary(b""));
+        assert!(!is_binary(b"{\"a\": 1}\r\n\tplain text\x1b[0m"));
+        assert!(!is_binary("日本語のテキスト".as_bytes()));
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode_text(b"hello"), Some("hello".to_string()));
+        assert_eq!(decode_text(b"hello\0world"), None);
+        assert_eq!(decode_text(&[0xFF, 0xFE, 0xFD]), None);
+    }
+
+    #[test]
+    fn test_decode_text_auto() {
+        let auto = |data: &[u8], declared| {
+            decode_text_auto(data, declared).map(|(text, encoding)| (text, encoding.name()))
+        };
+        assert_eq!(auto(b"hello", None), Some(("hello".to_string(), "UTF-8")));
+        // The byte order mark wins over the declared charset
+        assert_eq!(
+            auto(&[0xFF, 0xFE, b'h', 0, b'i', 0], Some("iso-8859-1")),
+            Some(("hi".to_string(), "UTF-16LE"))
+        );
+        assert_eq!(
+            auto(b"caf\xe9", Some("\"ISO-8859-1\"")),
+            Some(("café".to_string(), "windows-1252"))
+        );
+        // A declared charset the body is not valid in is ignored
+        assert_eq!(
+            auto("日本語".as_bytes(), Some("shift_jis")),
+            Some(("日本語".to_string(), "UTF-8"))
+        );
+        assert_eq!(
+            auto(&[0x93, 0xFA, 0x96, 0x7B, 0x8C, 0xEA], None),
+            Some(("日本語".to_string(), "Shift_JIS"))
+        );
+        assert_eq!(
+            auto(b"caf\xe9", None),
+            Some(("café".to_string(), "windows-1252"))
+        );
+        assert_eq!(auto(b"hello\0world", None), None);
+    }
+
+    #[test]
+    fn test_charset_param() {
+        assert_eq!(charset_param("text/html; charset=EUC-JP"), Some("EUC-JP"));
+        assert_eq!(
+            charset_param("text/plain;format=flowed; Charset=\"utf-8\""),
+            Some("utf-8")
+        );
+        assert_eq!(charset_param("text/html"), None);
+    }
+
+    #[test]
+    fn every_advertised_encoding_has_a_decoder() {
+        for encoding in SUPPORTED_ENCODINGS {
+            assert!(decoder_for(encoding).is_some(), "No decoder for {encoding}");
+            assert!(compression_levels(encoding).is_ok());
+        }
+        assert!(decoder_for(ENC_IDENTITY).is_none());
+        assert_eq!(accept_encoding(), "gzip, deflate, zstd");
+    }
 }
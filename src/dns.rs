@@ -0,0 +1,117 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use hickory_resolver::config::{
+    NameServerConfigGroup, ResolverConfig, ResolverOpts, ServerOrderingStrategy,
+};
+use hickory_resolver::TokioAsyncResolver;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+use crate::timing::StageClock;
+
+/// Resolves the host names with the DNS servers given by `--dns-server`
+/// instead of the system resolver, timing each lookup. The servers are
+/// asked in the order given, the next one being tried when one fails.
+pub struct DnsServerResolver {
+    resolver: Arc<TokioAsyncResolver>,
+    clock: StageClock,
+}
+
+impl DnsServerResolver {
+    pub fn new(servers: &[SocketAddr], clock: StageClock) -> Self {
+        let mut group = NameServerConfigGroup::new();
+        for server in servers {
+            group.merge(NameServerConfigGroup::from_ips_clear(
+                &[server.ip()],
+                server.port(),
+                true,
+            ));
+        }
+        // No search domain, like the names of URLs which are absolute
+        let config = ResolverConfig::from_parts(None, vec![], group);
+        let mut opts = ResolverOpts::default();
+        opts.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+        Self {
+            resolver: Arc::new(TokioAsyncResolver::tokio(config, opts)),
+            clock,
+        }
+    }
+}
+
+impl Resolve for DnsServerResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.resolver.clone();
+        let clock = self.clock.clone();
+        Box::pin(async move {
+            let started = Instant::now();
+            let lookup = resolver.lookup_ip(name.as_str()).await;
+            clock.add_dns(started.elapsed());
+            let addrs: Addrs = Box::new(
+                lookup?
+                    .iter()
+                    .map(|ip| SocketAddr::new(ip, 0))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            );
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+    use std::str::FromStr;
+
+    const QTYPE_A: [u8; 2] = [0, 1];
+
+    // Answers the A queries with 10.1.2.3 and the others with nothing,
+    // until `queries` of them have been answered
+    fn serve_dns(socket: UdpSocket, queries: usize) {
+        let mut buf = [0u8; 512];
+        for _ in 0..queries {
+            let (len, peer) = socket.recv_from(&mut buf).unwrap();
+            // The question follows the 12 bytes header: the labels of
+            // the name, then its type and class
+            let mut end = 12;
+            while buf[end] != 0 {
+                end += buf[end] as usize + 1;
+            }
+            let question = &buf[12..end + 5];
+            let is_a = question[question.len() - 4..question.len() - 2] == QTYPE_A;
+            assert!(end + 5 <= len);
+
+            let mut reply = vec![buf[0], buf[1], 0x81, 0x80, 0, 1, 0, is_a as u8, 0, 0, 0, 0];
+            reply.extend_from_slice(question);
+            if is_a {
+                // Name pointer to the question, A, IN, TTL 60, 4 bytes
+                reply.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 10, 1, 2, 3]);
+            }
+            socket.send_to(&reply, peer).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_with_dns_server() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = socket.local_addr().unwrap();
+        let dns = std::thread::spawn(move || serve_dns(socket, 1));
+
+        let clock = StageClock::default();
+        let resolver = DnsServerResolver::new(&[server], clock.clone());
+        let addrs: Vec<_> = resolver
+            .resolve(Name::from_str("api.internal.example").unwrap())
+            .await
+            .unwrap()
+            .collect();
+        dns.join().unwrap();
+
+        assert_eq!(
+            addrs,
+            [SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3)), 0)]
+        );
+        assert!(clock.stages().0 > std::time::Duration::ZERO);
+    }
+}
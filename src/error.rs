@@ -0,0 +1,252 @@
+use reqwest::header::{InvalidHeaderName, InvalidHeaderValue};
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::fmt;
+
+pub const ERROR_FORMAT_TEXT: &str = "text";
+pub const ERROR_FORMAT_JSON: &str = "json";
+
+/// Raised when the server answers with a non-success status code.
+#[derive(Debug)]
+pub struct HttpStatusError {
+    status: StatusCode,
+    url: String,
+    body: String,
+}
+
+impl HttpStatusError {
+    pub fn new(status: StatusCode, url: String, body: String) -> Self {
+        Self { status, url, body }
+    }
+}
+
+impl fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.status, self.body)
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// Raised when a -H header is not given as `Name: value`.
+#[derive(Debug, Clone)]
+pub struct MalformedHeaderError {
+    header: String,
+}
+
+impl MalformedHeaderError {
+    pub fn new(header: &str) -> Self {
+        Self {
+            header: header.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for MalformedHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid header format '{}', expected 'Name: value'",
+            self.header
+        )
+    }
+}
+
+impl std::error::Error for MalformedHeaderError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    Timeout,
+    Connect,
+    Redirect,
+    Request,
+    Body,
+    Decode,
+    Http,
+    Other,
+}
+
+/// Machine readable description of a failed run, printed to stderr
+/// with `--error-format json`.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    error: String,
+    kind: ErrorKind,
+    url: Option<String>,
+    status: Option<u16>,
+}
+
+impl ErrorReport {
+    /// Classifies `err` by walking its cause chain. `url` is used when
+    /// none of the causes carries the URL of the failed request (e.g.
+    /// the request could not even be built).
+    pub fn new(err: &anyhow::Error, url: Option<String>) -> Self {
+        let mut report = Self {
+            label: None,
+            request_id: None,
+            error: format!("{err:#}"),
+            kind: ErrorKind::Other,
+            url,
+            status: None,
+        };
+
+        for cause in err.chain() {
+            if let Some(e) = cause.downcast_ref::<HttpStatusError>() {
+                report.kind = ErrorKind::Http;
+                report.url = Some(e.url.clone());
+                report.status = Some(e.status.as_u16());
+                break;
+            }
+            if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                report.kind = classify_reqwest_error(e);
+                if let Some(url) = e.url() {
+                    report.url = Some(url.to_string());
+                }
+                report.status = e.status().map(|s| s.as_u16());
+                break;
+            }
+            if cause.downcast_ref::<InvalidHeaderName>().is_some()
+                || cause.downcast_ref::<InvalidHeaderValue>().is_some()
+                || cause.downcast_ref::<MalformedHeaderError>().is_some()
+            {
+                report.kind = ErrorKind::Request;
+                break;
+            }
+            if cause.downcast_ref::<serde_json::Error>().is_some() {
+                report.kind = ErrorKind::Decode;
+                break;
+            }
+        }
+
+        report
+    }
+
+    /// Tags the report with the `--label` of the request.
+    pub fn with_label(mut self, label: Option<&String>) -> Self {
+        self.label = label.cloned();
+        self
+    }
+
+    /// Tags the report with the id sent with `--request-id`.
+    pub fn with_request_id(mut self, request_id: Option<&String>) -> Self {
+        self.request_id = request_id.cloned();
+        self
+    }
+
+    #[cfg(test)]
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+fn classify_reqwest_error(e: &reqwest::Error) -> ErrorKind {
+    if e.is_timeout() {
+        ErrorKind::Timeout
+    } else if e.is_connect() {
+        ErrorKind::Connect
+    } else if e.is_redirect() {
+        ErrorKind::Redirect
+    } else if e.is_status() {
+        ErrorKind::Http
+    } else if e.is_body() {
+        ErrorKind::Body
+    } else if e.is_decode() {
+        ErrorKind::Decode
+    } else if e.is_builder() || e.is_request() {
+        ErrorKind::Request
+    } else {
+        ErrorKind::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Context};
+
+    #[test]
+    fn test_report_for_plain_error() {
+        let err = anyhow!("Invalid header name: bad header");
+        let report = ErrorReport::new(&err, Some("/path".to_string()));
+        assert_eq!(report.kind(), ErrorKind::Other);
+        assert_eq!(
+            report.to_json(),
+            r#"{"error":"Invalid header name: bad header","kind":"other","url":"/path","status":null}"#
+        );
+    }
+
+    #[test]
+    fn test_report_for_malformed_header() {
+        let err: anyhow::Error = MalformedHeaderError::new("bogus").into();
+        let report = ErrorReport::new(&err, None);
+        assert_eq!(report.kind(), ErrorKind::Request);
+    }
+
+    #[test]
+    fn test_report_for_http_status_error() {
+        let err: anyhow::Error = HttpStatusError::new(
+            StatusCode::NOT_FOUND,
+            "http://localhost/missing".to_string(),
+            "nope".to_string(),
+        )
+        .into();
+        let report = ErrorReport::new(&err, None);
+        assert_eq!(report.kind(), ErrorKind::Http);
+        assert_eq!(
+            report.to_json(),
+            r#"{"error":"404 Not Found: nope","kind":"http","url":"http://localhost/missing","status":404}"#
+        );
+    }
+
+    #[test]
+    fn test_report_with_label() {
+        let err = anyhow!("boom");
+        let report = ErrorReport::new(&err, None).with_label(Some(&"nightly-sync".to_string()));
+        assert_eq!(
+            report.to_json(),
+            r#"{"label":"nightly-sync","error":"boom","kind":"other","url":null,"status":null}"#
+        );
+
+        let report = ErrorReport::new(&err, None).with_request_id(Some(&"abc-123".to_string()));
+        assert_eq!(
+            report.to_json(),
+            r#"{"request_id":"abc-123","error":"boom","kind":"other","url":null,"status":null}"#
+        );
+    }
+
+    #[test]
+    fn test_report_for_json_decode_error() {
+        let err = serde_json::from_str::<serde_json::Value>("{")
+            .context("Failed to parse response")
+            .unwrap_err();
+        let report = ErrorReport::new(&err, None);
+        assert_eq!(report.kind(), ErrorKind::Decode);
+        assert!(report
+            .to_json()
+            .contains(r#""error":"Failed to parse response: "#));
+    }
+
+    #[tokio::test]
+    async fn test_report_for_connect_error() {
+        // Bind and drop a listener to get a port nobody listens on
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let url = format!("http://127.0.0.1:{port}/");
+        let err: anyhow::Error = reqwest::get(&url).await.unwrap_err().into();
+        let report = ErrorReport::new(&err, Some(url.clone()));
+        assert_eq!(report.kind(), ErrorKind::Connect);
+        assert_eq!(report.url, Some(url));
+        assert_eq!(report.status, None);
+    }
+}
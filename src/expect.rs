@@ -0,0 +1,237 @@
+use anyhow::Context;
+use serde_json::Value;
+
+use crate::http::HttpResponse;
+use crate::utils::Result;
+
+// Lines of context kept around each change of the diff
+const CONTEXT_LINES: usize = 2;
+// Beyond this many line pairs the diff is not computed, only the first
+// differing line is reported
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Compares the body of `res` with the fixture file at `path` and fails
+/// with a diff of the two when they differ.
+pub fn expect_body_file(path: &str, res: &HttpResponse) -> Result<()> {
+    let path = shellexpand::tilde(path).to_string();
+    let expected =
+        std::fs::read(&path).with_context(|| format!("Failed to read body fixture '{path}'"))?;
+    match compare_body(&expected, res.bytes(), res.json()) {
+        None => Ok(()),
+        Some(diff) => Err(anyhow::anyhow!(
+            "Response body does not match '{path}' (- expected, + actual):\n{diff}"
+        )),
+    }
+}
+
+/// The diff of the `expected` and `actual` bodies, or None if they are
+/// the same. When both are JSON, they are compared as values, so that
+/// key order and whitespace do not matter. Otherwise the bytes are.
+fn compare_body(expected: &[u8], actual: &[u8], actual_json: Option<&Value>) -> Option<String> {
+    let expected_json = serde_json::from_slice::<Value>(expected).ok();
+    let actual_json = actual_json
+        .cloned()
+        .or_else(|| serde_json::from_slice(actual).ok());
+    if let (Some(expected), Some(actual)) = (expected_json, actual_json) {
+        if expected == actual {
+            return None;
+        }
+        return Some(diff_lines(
+            &canonical_json(&expected),
+            &canonical_json(&actual),
+        ));
+    }
+
+    if expected == actual {
+        return None;
+    }
+    match (std::str::from_utf8(expected), std::str::from_utf8(actual)) {
+        (Ok(expected), Ok(actual)) => Some(diff_lines(expected, actual)),
+        _ => Some(format!(
+            "binary bodies differ ({} bytes expected, {} bytes received)",
+            expected.len(),
+            actual.len()
+        )),
+    }
+}
+
+// Pretty-printed with the keys sorted, so that equal objects print alike
+fn canonical_json(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                Value::Object(
+                    entries
+                        .into_iter()
+                        .map(|(k, v)| (k.clone(), sorted(v)))
+                        .collect(),
+                )
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            _ => value.clone(),
+        }
+    }
+    serde_json::to_string_pretty(&sorted(value)).unwrap()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// A line diff of `expected` and `actual`: `-` for the lines expected
+/// only, `+` for the ones received only, with a few lines of context.
+fn diff_lines(expected: &str, actual: &str) -> String {
+    let expected: Vec<_> = expected.lines().collect();
+    let actual: Vec<_> = actual.lines().collect();
+    if expected.len().saturating_mul(actual.len()) > MAX_DIFF_CELLS {
+        return first_difference(&expected, &actual);
+    }
+
+    let lines = line_changes(&expected, &actual);
+    let changed: Vec<_> = lines.iter().map(|l| !matches!(l, Line::Same(_))).collect();
+    if !changed.contains(&true) {
+        return "  (the bodies differ in whitespace or line endings only)".to_string();
+    }
+    let near_change = |i: usize| {
+        let from = i.saturating_sub(CONTEXT_LINES);
+        let to = (i + CONTEXT_LINES + 1).min(lines.len());
+        changed[from..to].iter().any(|&c| c)
+    };
+
+    let mut out = vec![];
+    let mut skipped = false;
+    for (i, line) in lines.iter().enumerate() {
+        if !near_change(i) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            out.push("  ...".to_string());
+            skipped = false;
+        }
+        out.push(match line {
+            Line::Same(l) => format!("  {l}"),
+            Line::Removed(l) => format!("- {l}"),
+            Line::Added(l) => format!("+ {l}"),
+        });
+    }
+    if skipped {
+        out.push("  ...".to_string());
+    }
+    out.join("\n")
+}
+
+// The longest common subsequence of the lines, walked from the start
+fn line_changes<'a>(expected: &[&'a str], actual: &[&'a str]) -> Vec<Line<'a>> {
+    let (n, m) = (expected.len(), actual.len());
+    // common[i][j]: length of the LCS of expected[i..] and actual[j..]
+    let mut common = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            common[i][j] = match expected[i] == actual[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = vec![];
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            lines.push(Line::Same(expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(Line::Removed(expected[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(actual[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+fn first_difference(expected: &[&str], actual: &[&str]) -> String {
+    let line = expected
+        .iter()
+        .zip(actual)
+        .position(|(e, a)| e != a)
+        .unwrap_or(expected.len().min(actual.len()));
+    let at = |lines: &[&str]| {
+        lines
+            .get(line)
+            .copied()
+            .unwrap_or("<end of body>")
+            .to_string()
+    };
+    format!(
+        "first difference at line {}:\n- {}\n+ {}",
+        line + 1,
+        at(expected),
+        at(actual)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_json_semantically() {
+        let expected = b"{\"a\": 1, \"b\": [1, 2]}";
+        let actual = b"{\"b\":[1,2],\"a\":1}";
+        assert_eq!(compare_body(expected, actual, None), None);
+
+        let actual_json = serde_json::json!({"b": [1, 3], "a": 1});
+        assert_eq!(
+            compare_body(expected, b"ignored", Some(&actual_json)).unwrap(),
+            "  ...\n    \"b\": [\n      1,\n-     2\n+     3\n    ]\n  }"
+        );
+    }
+
+    #[test]
+    fn test_compare_text() {
+        assert_eq!(compare_body(b"ok\n", b"ok\n", None), None);
+        // Not JSON, so the bytes must match
+        assert!(compare_body(b"ok\n", b"ok", None).is_some());
+        assert_eq!(
+            compare_body(b"a\nb\nc\nd\ne\nf\ng\n", b"a\nb\nc\nd\nX\nf\ng\n", None).unwrap(),
+            "  ...\n  c\n  d\n- e\n+ X\n  f\n  g"
+        );
+        assert_eq!(
+            compare_body(&[0xff, 0], &[0xff, 1, 2], None).unwrap(),
+            "binary bodies differ (2 bytes expected, 3 bytes received)"
+        );
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        assert_eq!(
+            diff_lines("1\n2\n3\n4\n5\n6\n7\n8\n9", "1\n3\n4\n5\n6\n7\n8\n9\n10"),
+            "  1\n- 2\n  3\n  4\n  ...\n  8\n  9\n+ 10"
+        );
+        assert_eq!(
+            diff_lines("a\r\n", "a\n"),
+            "  (the bodies differ in whitespace or line endings only)"
+        );
+    }
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(
+            first_difference(&["a", "b"], &["a", "c", "d"]),
+            "first difference at line 2:\n- b\n+ c"
+        );
+        assert_eq!(
+            first_difference(&["a"], &["a", "b"]),
+            "first difference at line 2:\n- <end of body>\n+ b"
+        );
+    }
+}
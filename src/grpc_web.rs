@@ -0,0 +1,231 @@
+use serde_json::Value;
+
+use crate::utils::Result;
+
+// The flags byte and the 4 bytes big-endian length of the payload
+const FRAME_HEADER_LEN: usize = 5;
+// Set on the frame carrying the trailers, after the messages
+const FLAG_TRAILER: u8 = 0x80;
+// Set when the payload is compressed with the grpc-encoding
+const FLAG_COMPRESSED: u8 = 0x01;
+// Payload bytes of a protobuf message printed per line
+const HEX_LINE_BYTES: usize = 16;
+
+/// A length-prefixed frame of a gRPC-Web response: a message, or the
+/// trailers which end the response.
+/// See https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-WEB.md
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame<'a> {
+    pub trailer: bool,
+    pub compressed: bool,
+    pub payload: &'a [u8],
+}
+
+/// Whether the Content-Type is a binary gRPC-Web one:
+/// `application/grpc-web`, or its `+proto` and `+json` variants.
+pub fn is_grpc_web(content_type: Option<&str>) -> bool {
+    let Some(content_type) = content_type else {
+        return false;
+    };
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    [
+        "application/grpc-web",
+        "application/grpc-web+proto",
+        "application/grpc-web+json",
+    ]
+    .iter()
+    .any(|m| mime.eq_ignore_ascii_case(m))
+}
+
+/// Splits a gRPC-Web response body into its frames.
+pub fn parse_frames(body: &[u8]) -> Result<Vec<Frame<'_>>> {
+    let mut frames = vec![];
+    let mut rest = body;
+    while !rest.is_empty() {
+        let number = frames.len() + 1;
+        if rest.len() < FRAME_HEADER_LEN {
+            return Err(anyhow::anyhow!(
+                "gRPC-Web frame {number} is truncated: {} bytes left, a frame header is {FRAME_HEADER_LEN}",
+                rest.len()
+            ));
+        }
+        let flags = rest[0];
+        let len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+        rest = &rest[FRAME_HEADER_LEN..];
+        if rest.len() < len {
+            return Err(anyhow::anyhow!(
+                "gRPC-Web frame {number} is truncated: {len} bytes announced, {} received",
+                rest.len()
+            ));
+        }
+        frames.push(Frame {
+            trailer: flags & FLAG_TRAILER != 0,
+            compressed: flags & FLAG_COMPRESSED != 0,
+            payload: &rest[..len],
+        });
+        rest = &rest[len..];
+    }
+    Ok(frames)
+}
+
+/// Formats the frames of a gRPC-Web response one after the other, each
+/// under a line telling what it is. The messages of the `+json` variant
+/// are printed as JSON, pretty-printed when `pretty`, the others as hex
+/// since they are not decoded. The trailers are printed as headers.
+pub fn format_frames(frames: &[Frame], json: bool, pretty: bool) -> String {
+    let mut out = vec![];
+    let mut messages = 0;
+    for frame in frames {
+        let compressed = match frame.compressed {
+            true => ", compressed",
+            false => "",
+        };
+        if frame.trailer {
+            out.push(format!(
+                "--- trailers ({} bytes{compressed}) ---",
+                frame.payload.len()
+            ));
+        } else {
+            messages += 1;
+            out.push(format!(
+                "--- message {messages} ({} bytes{compressed}) ---",
+                frame.payload.len()
+            ));
+        }
+        let payload = match (frame.trailer, frame.compressed) {
+            (_, true) => hex_lines(frame.payload),
+            (true, false) => trailer_lines(frame.payload),
+            (false, false) if json => json_payload(frame.payload, pretty),
+            (false, false) => hex_lines(frame.payload),
+        };
+        if !payload.is_empty() {
+            out.push(payload);
+        }
+    }
+    out.join("\n")
+}
+
+// The trailers are HTTP/1 header lines, `grpc-status:0\r\n...`
+fn trailer_lines(payload: &[u8]) -> String {
+    String::from_utf8_lossy(payload)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_once(':') {
+            Some((name, value)) => format!("{}: {}", name.trim(), value.trim()),
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn json_payload(payload: &[u8], pretty: bool) -> String {
+    match serde_json::from_slice::<Value>(payload) {
+        Ok(json) if pretty => serde_json::to_string_pretty(&json).unwrap(),
+        _ => String::from_utf8_lossy(payload).to_string(),
+    }
+}
+
+fn hex_lines(payload: &[u8]) -> String {
+    payload
+        .chunks(HEX_LINE_BYTES)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(flags: u8, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![flags];
+        bytes.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test]
+    fn test_is_grpc_web() {
+        assert!(is_grpc_web(Some("application/grpc-web")));
+        assert!(is_grpc_web(Some("application/grpc-web+proto")));
+        assert!(is_grpc_web(Some(
+            "Application/gRPC-Web+JSON; charset=utf-8"
+        )));
+        assert!(!is_grpc_web(Some("application/grpc-web-text")));
+        assert!(!is_grpc_web(Some("application/json")));
+        assert!(!is_grpc_web(None));
+    }
+
+    #[test]
+    fn test_parse_frames() {
+        let body = [
+            frame(0, b"{\"id\":1}"),
+            frame(0, b""),
+            frame(FLAG_COMPRESSED, &[1, 2]),
+            frame(FLAG_TRAILER, b"grpc-status:0\r\n"),
+        ]
+        .concat();
+        let frames = parse_frames(&body).unwrap();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0].payload, b"{\"id\":1}");
+        assert!(!frames[0].trailer && !frames[0].compressed);
+        assert!(frames[1].payload.is_empty());
+        assert!(frames[2].compressed);
+        assert!(frames[3].trailer);
+        assert!(parse_frames(b"").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_truncated_frames() {
+        let err = parse_frames(&[0, 0, 0]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "gRPC-Web frame 1 is truncated: 3 bytes left, a frame header is 5"
+        );
+
+        let mut body = frame(0, b"ok");
+        body.extend_from_slice(&[0, 0, 0, 0, 9, b'a']);
+        let err = parse_frames(&body).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "gRPC-Web frame 2 is truncated: 9 bytes announced, 1 received"
+        );
+    }
+
+    #[test]
+    fn test_format_json_frames() {
+        let body = [
+            frame(0, b"{\"id\":1}"),
+            frame(0, b"not json"),
+            frame(FLAG_TRAILER, b"grpc-status:0\r\ngrpc-message: OK\r\n"),
+        ]
+        .concat();
+        let frames = parse_frames(&body).unwrap();
+        assert_eq!(
+            format_frames(&frames, true, true),
+            "--- message 1 (8 bytes) ---\n{\n  \"id\": 1\n}\n\
+             --- message 2 (8 bytes) ---\nnot json\n\
+             --- trailers (33 bytes) ---\ngrpc-status: 0\ngrpc-message: OK"
+        );
+        assert!(format_frames(&frames, true, false).contains("\n{\"id\":1}\n"));
+    }
+
+    #[test]
+    fn test_format_proto_frames() {
+        let payload: Vec<u8> = (0..18).collect();
+        let body = [frame(0, &payload), frame(FLAG_COMPRESSED, &[0xff])].concat();
+        let frames = parse_frames(&body).unwrap();
+        assert_eq!(
+            format_frames(&frames, false, true),
+            "--- message 1 (18 bytes) ---\n\
+             00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n10 11\n\
+             --- message 2 (1 bytes, compressed) ---\nff"
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+
+use crate::http::{HttpRequestArgs, RequestHeaders};
+use crate::url::{Url, UrlPath};
+use crate::utils::Result;
+
+// Headers recorded by the browser that must not be replayed as-is.
+// HTTP/2 pseudo headers (":authority" etc.) are skipped as well. The
+// browser accepts encodings (e.g. br) that can't be decoded here, so
+// the default Accept-Encoding is sent instead.
+const SKIPPED_HEADERS: [&str; 4] = ["host", "content-length", "connection", "accept-encoding"];
+
+// Only the fields needed to replay a request are modelled.
+// See http://www.softwareishard.com/blog/har-12-spec/
+#[derive(Debug, Deserialize)]
+struct HarFile {
+    log: HarLog,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarLog {
+    #[serde(default)]
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarEntry {
+    request: HarEntryRequest,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HarEntryRequest {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<HarHeader>,
+    post_data: Option<HarPostData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarHeader {
+    name: String,
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HarPostData {
+    text: Option<String>,
+}
+
+/// A request recorded in a HAR file, ready to be merged into the
+/// command line arguments.
+#[derive(Debug, Clone)]
+pub struct HarRequest {
+    method: String,
+    url: Url,
+    body: Option<String>,
+    headers: RequestHeaders,
+}
+
+impl From<HarEntryRequest> for HarRequest {
+    fn from(req: HarEntryRequest) -> Self {
+        let mut headers = RequestHeaders::new();
+        for header in req.headers {
+            let name = header.name.to_lowercase();
+            if name.starts_with(':') || SKIPPED_HEADERS.contains(&name.as_str()) {
+                continue;
+            }
+            // HTTP/2 splits the cookies across several headers while
+            // HTTP/1.1 allows only one, so join them back
+            match headers.get_mut(&name) {
+                Some(cookie) if name == "cookie" => {
+                    cookie.push_str("; ");
+                    cookie.push_str(&header.value);
+                }
+                _ => {
+                    headers.append(name, header.value);
+                }
+            }
+        }
+
+        Self {
+            method: req.method.to_uppercase(),
+            url: Url::parse(&req.url),
+            body: req.post_data.and_then(|p| p.text).filter(|t| !t.is_empty()),
+            headers,
+        }
+    }
+}
+
+impl HarRequest {
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+}
+
+impl HttpRequestArgs for HarRequest {
+    fn method(&self) -> Option<&String> {
+        Some(&self.method)
+    }
+
+    fn url_path(&self) -> Option<&UrlPath> {
+        self.url.to_url_path()
+    }
+
+    fn body(&self) -> Option<&String> {
+        self.body.as_ref()
+    }
+
+    fn headers(&self) -> &RequestHeaders {
+        &self.headers
+    }
+
+    fn compression(&self) -> Option<&String> {
+        None
+    }
+
+    fn compression_level(&self) -> Option<u32> {
+        None
+    }
+
+    fn decode_as(&self) -> Option<&String> {
+        None
+    }
+
+    fn charset(&self) -> Option<&String> {
+        None
+    }
+
+    fn discard_body(&self) -> bool {
+        false
+    }
+
+    fn no_decompress(&self) -> bool {
+        false
+    }
+
+    fn normalize_path(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug)]
+pub struct Har {
+    requests: Vec<HarRequest>,
+}
+
+impl Har {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read HAR file '{path}'"))?;
+        Self::parse(&content).with_context(|| format!("Failed to parse HAR file '{path}'"))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let file: HarFile = serde_json::from_str(content)?;
+        Ok(Self {
+            requests: file
+                .log
+                .entries
+                .into_iter()
+                .map(|e| e.request.into())
+                .collect(),
+        })
+    }
+
+    pub fn requests(&self) -> &[HarRequest] {
+        &self.requests
+    }
+
+    /// Returns the request of the entry at `index` (0-based, in the
+    /// order listed by `--list`).
+    pub fn request(&self, index: usize) -> Result<&HarRequest> {
+        self.requests.get(index).ok_or_else(|| {
+            anyhow!(
+                "HAR entry {index} not found, the file has {} entries",
+                self.requests.len()
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const TEST_HAR: &str = r#"{
+        "log": {
+            "version": "1.2",
+            "creator": {"name": "WebInspector", "version": "537.36"},
+            "entries": [
+                {
+                    "startedDateTime": "2025-01-01T00:00:00.000Z",
+                    "request": {
+                        "method": "get",
+                        "url": "https://api.example.com/items?page=2",
+                        "httpVersion": "HTTP/2",
+                        "headers": [
+                            {"name": ":authority", "value": "api.example.com"},
+                            {"name": "Accept", "value": "application/json"},
+                            {"name": "Accept-Encoding", "value": "gzip, deflate, br, zstd"},
+                            {"name": "Cookie", "value": "a=1"},
+                            {"name": "cookie", "value": "b=2"}
+                        ],
+                        "queryString": [{"name": "page", "value": "2"}]
+                    },
+                    "response": {"status": 200}
+                },
+                {
+                    "request": {
+                        "method": "POST",
+                        "url": "http://localhost:8080/items",
+                        "headers": [
+                            {"name": "Content-Type", "value": "application/json"},
+                            {"name": "Content-Length", "value": "13"}
+                        ],
+                        "postData": {"mimeType": "application/json", "text": "{\"name\":\"a\"}"}
+                    }
+                }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_har_entries() {
+        let har = Har::parse(TEST_HAR).unwrap();
+        assert_eq!(har.requests().len(), 2);
+
+        let get = har.request(0).unwrap();
+        assert_eq!(get.method(), Some(&"GET".to_string()));
+        assert_eq!(
+            get.url().to_string(),
+            "https://api.example.com/items?page=2"
+        );
+        assert_eq!(get.body(), None);
+        assert_eq!(get.headers().keys_len(), 2);
+        assert_eq!(get.headers().get("accept").unwrap(), "application/json");
+        assert_eq!(get.headers().get("cookie").unwrap(), "a=1; b=2");
+        assert!(!get.headers().contains_key("accept-encoding"));
+
+        let post = har.request(1).unwrap();
+        assert_eq!(post.method(), Some(&"POST".to_string()));
+        assert_eq!(post.url().host(), Some(&"localhost".to_string()));
+        assert_eq!(post.url().port(), Some(8080));
+        assert_eq!(post.url_path().unwrap().path(), "/items");
+        assert_eq!(post.body(), Some(&"{\"name\":\"a\"}".to_string()));
+        assert!(!post.headers().contains_key("content-length"));
+    }
+
+    #[test]
+    fn test_request_out_of_range() {
+        let har = Har::parse(TEST_HAR).unwrap();
+        let err = har.request(2).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "HAR entry 2 not found, the file has 2 entries"
+        );
+    }
+
+    #[test]
+    fn test_load_har_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(TEST_HAR.as_bytes()).unwrap();
+
+        let har = Har::load(file.path().to_str().unwrap()).unwrap();
+        assert_eq!(har.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_load_invalid_har_file() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"{\"log\": 1}").unwrap();
+        let path = file.path().to_str().unwrap().to_string();
+
+        let err = Har::load(&path).unwrap_err();
+        assert!(err.to_string().contains("Failed to parse HAR file"));
+        assert!(Har::load("/nonexistent/file.har").is_err());
+    }
+}
@@ -1,14 +1,24 @@
-use crate::http::HttpConnectionProfile;
+use crate::http::{ConnectionPolicy, HttpConnectionProfile, ProfileHeaders, ResolveEntry};
+use crate::oauth::OAuth2Config;
+use crate::sigv4::AwsSigV4Config;
 use crate::stdio::{ask, ask_binary, ask_no_space_string, ask_path};
 use crate::url::Endpoint;
-use crate::utils::Result;
+use crate::utils::{parse_seconds, parse_size, Result};
 
 use anyhow::{anyhow, Context};
 use ini::{Ini, Properties};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
 
 pub const DEFAULT_INI_FILE_PATH: &str = "~/.httpc/profile";
+pub const DEFAULT_PROFILE: &str = "default";
 pub const PROFILE_BLANK: &str = "none";
+pub const PROFILE_INLINE: &str = "inline";
+
+// Kept in the general section, above the profiles
+const INI_DEFAULT_PROFILE: &str = "default_profile";
 
 const INI_HOST: &str = "host";
 const INI_USER: &str = "user";
@@ -16,8 +26,50 @@ const INI_PASSWORD: &str = "password";
 const INI_CA_CERT: &str = "ca_cert";
 const INI_INSECURE: &str = "insecure";
 const INI_PROXY: &str = "proxy";
-
-#[derive(Debug)]
+const INI_PROXY_USER: &str = "proxy_user";
+const INI_PROXY_PASSWORD: &str = "proxy_password";
+const INI_CONNECT_TIMEOUT: &str = "connect_timeout";
+const INI_MAX_TIME: &str = "max_time";
+const INI_RETRIES: &str = "retries";
+const INI_RETRY_ALL_METHODS: &str = "retry_all_methods";
+const INI_MAX_REDIRECTS: &str = "max_redirects";
+const INI_FOLLOW: &str = "follow";
+const INI_MAX_HEADER_SIZE: &str = "max_header_size";
+const INI_LOCATION_TRUSTED: &str = "location_trusted";
+const INI_ABORT_ON_REDIRECT_LOOP: &str = "abort_on_redirect_loop";
+const INI_OAUTH_TOKEN_URL: &str = "oauth_token_url";
+const INI_OAUTH_CLIENT_ID: &str = "oauth_client_id";
+const INI_OAUTH_CLIENT_SECRET: &str = "oauth_client_secret";
+const INI_OAUTH_SCOPE: &str = "oauth_scope";
+const INI_AWS_ACCESS_KEY_ID: &str = "aws_access_key_id";
+const INI_AWS_SECRET_ACCESS_KEY: &str = "aws_secret_access_key";
+const INI_AWS_SESSION_TOKEN: &str = "aws_session_token";
+const INI_AWS_REGION: &str = "aws_region";
+const INI_AWS_SERVICE: &str = "aws_service";
+
+// The keys accepted by `--profile-inline`. The OAuth2 and AWS settings
+// are left out as they are not merged into the loaded profile.
+const INLINE_KEYS: [&str; 17] = [
+    INI_HOST,
+    INI_USER,
+    INI_PASSWORD,
+    INI_CA_CERT,
+    INI_INSECURE,
+    INI_PROXY,
+    INI_PROXY_USER,
+    INI_PROXY_PASSWORD,
+    INI_CONNECT_TIMEOUT,
+    INI_MAX_TIME,
+    INI_RETRIES,
+    INI_RETRY_ALL_METHODS,
+    INI_MAX_REDIRECTS,
+    INI_FOLLOW,
+    INI_MAX_HEADER_SIZE,
+    INI_LOCATION_TRUSTED,
+    INI_ABORT_ON_REDIRECT_LOOP,
+];
+
+#[derive(Debug, Clone)]
 pub struct IniProfile {
     name: String,
     server: Option<Endpoint>,
@@ -27,6 +79,14 @@ pub struct IniProfile {
     ca_cert: Option<String>,
     headers: HashMap<String, String>,
     proxy: Option<Endpoint>,
+    proxy_user: Option<String>,
+    proxy_password: Option<String>,
+    policy: ConnectionPolicy,
+    oauth2: Option<OAuth2Config>,
+    aws_sigv4: Option<AwsSigV4Config>,
+    /// Given on the command line only
+    resolve: Vec<ResolveEntry>,
+    dns_servers: Vec<SocketAddr>,
 }
 
 impl HttpConnectionProfile for IniProfile {
@@ -50,13 +110,33 @@ impl HttpConnectionProfile for IniProfile {
         self.ca_cert.as_ref()
     }
 
-    fn headers(&self) -> &HashMap<String, String> {
-        &self.headers
+    fn headers(&self) -> ProfileHeaders<'_> {
+        Cow::Borrowed(&self.headers)
     }
 
     fn proxy(&self) -> Option<&Endpoint> {
         self.proxy.as_ref()
     }
+
+    fn proxy_user(&self) -> Option<&String> {
+        self.proxy_user.as_ref()
+    }
+
+    fn proxy_password(&self) -> Option<&String> {
+        self.proxy_password.as_ref()
+    }
+
+    fn policy(&self) -> ConnectionPolicy {
+        self.policy
+    }
+
+    fn resolve(&self) -> &[ResolveEntry] {
+        &self.resolve
+    }
+
+    fn dns_servers(&self) -> &[SocketAddr] {
+        &self.dns_servers
+    }
 }
 
 impl IniProfile {
@@ -82,14 +162,63 @@ impl IniProfile {
             self.ca_cert = other.ca_cert().cloned();
         }
         if !other.headers().is_empty() {
-            for (k, v) in other.headers() {
+            for (k, v) in other.headers().iter() {
                 self.headers.insert(k.clone(), v.clone());
             }
         }
         if other.proxy().is_some() {
             self.proxy = other.proxy().cloned();
         }
+        if other.proxy_user().is_some() {
+            self.proxy_user = other.proxy_user().cloned();
+            self.proxy_password = other.proxy_password().cloned();
+        }
+        self.policy.merge(&other.policy());
+        self.resolve.extend(other.resolve().iter().cloned());
+        if !other.dns_servers().is_empty() {
+            self.dns_servers = other.dns_servers().to_vec();
+        }
+
+        self
+    }
+
+    /// Drop the credentials and the Authorization header so that only
+    /// the auth given explicitly on the command line is used.
+    pub fn clear_auth(&mut self) -> &mut Self {
+        self.user = None;
+        self.password = None;
+        self.headers
+            .retain(|k, _| !k.eq_ignore_ascii_case("authorization"));
+        self
+    }
+
+    /// Set the credentials for the basic auth.
+    pub fn set_credentials(&mut self, user: &str, password: Option<&str>) -> &mut Self {
+        self.user = Some(user.to_string());
+        self.password = password.map(|p| p.to_string());
+        self
+    }
+
+    pub fn oauth2(&self) -> Option<&OAuth2Config> {
+        self.oauth2.as_ref()
+    }
 
+    pub fn aws_sigv4(&self) -> Option<&AwsSigV4Config> {
+        self.aws_sigv4.as_ref()
+    }
+
+    /// Replace the credentials with the given OAuth2 access token.
+    pub fn set_bearer_token(&mut self, token: &str) -> &mut Self {
+        self.clear_auth();
+        self.headers
+            .insert("authorization".to_string(), format!("Bearer {token}"));
+        self
+    }
+
+    /// Drop the default headers so that only the headers given on the
+    /// command line are sent.
+    pub fn clear_headers(&mut self) -> &mut Self {
+        self.headers.clear();
         self
     }
 }
@@ -104,6 +233,16 @@ impl IniProfileStore {
         Self { file_path }
     }
 
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    /// Whether the profile file exists. It does not until the first
+    /// profile is saved.
+    pub fn exists(&self) -> bool {
+        std::path::Path::new(&self.file_path).exists()
+    }
+
     pub fn get_profile(&self, name: &str) -> Result<Option<IniProfile>> {
         if name == PROFILE_BLANK {
             return Ok(Some(get_blank_profile()));
@@ -127,63 +266,45 @@ impl IniProfileStore {
             }
         };
 
-        let mut headers = HashMap::<String, String>::new();
-        for (key, value) in section.iter() {
-            // here, we'll pick up only ones start with at sign
-            if let Some(stripped) = key.strip_prefix("@") {
-                headers.insert(stripped.to_string().to_lowercase(), value.to_string());
-            }
-        }
+        parse_section(name, section).map(Some)
+    }
 
-        fn try_get<T>(section: &Properties, key: &str) -> Result<Option<T>>
-        where
-            T: std::str::FromStr,
-            T::Err: std::fmt::Debug,
-        {
-            match section.get(key) {
-                Some(s) => match s.parse::<T>() {
-                    Ok(value) => Ok(Some(value)),
-                    Err(e) => Err(anyhow!("Invalid value '{}' for '{}': {:?}", s, key, e)),
-                },
-                None => Ok(None),
-            }
+    /// The profile recorded with `set_default_profile`, if any.
+    pub fn get_default_profile(&self) -> Result<Option<String>> {
+        if !std::path::Path::new(&self.file_path).exists() {
+            return Ok(None);
         }
+        let ini = self.load_or_create()?;
+        Ok(ini
+            .general_section()
+            .get(INI_DEFAULT_PROFILE)
+            .map(|name| name.to_string()))
+    }
 
-        fn try_get_bool(section: &Properties, key: &str) -> Result<Option<bool>> {
-            match section.get(key) {
-                Some(s) => match s.to_lowercase().as_str() {
-                    "true" => Ok(Some(true)),
-                    "false" => Ok(Some(false)),
-                    _ => Err(anyhow!(
-                        "Invalid boolean value '{}' for '{}'. Expected 'true' or 'false'",
-                        s,
-                        key
-                    )),
-                },
-                None => Ok(None),
-            }
+    /// Records `name` as the profile to use when none is given. The
+    /// profile must exist in the file, except for the blank one.
+    pub fn set_default_profile(&self, name: &str) -> Result<()> {
+        if name != PROFILE_BLANK && self.get_profile(name)?.is_none() {
+            return Err(anyhow!(
+                "Profile '{name}' not found in '{}'",
+                self.file_path
+            ));
         }
-
-        let profile = IniProfile {
-            name: name.to_string(),
-            server: try_get::<Endpoint>(section, INI_HOST)
-                .with_context(|| format!("Failed to parse host for profile '{name}'"))?,
-            user: try_get(section, INI_USER)?,
-            password: try_get(section, INI_PASSWORD)?,
-            insecure: try_get_bool(section, INI_INSECURE)
-                .with_context(|| format!("Failed to parse insecure flag for profile '{name}'"))?,
-            ca_cert: try_get(section, INI_CA_CERT)?,
-            headers: headers.clone(),
-            proxy: try_get::<Endpoint>(section, INI_PROXY)
-                .with_context(|| format!("Failed to parse proxy for profile '{name}'"))?,
-        };
-
-        Ok(Some(profile))
+        let mut ini = self.load_or_create()?;
+        ini.with_general_section().set(INI_DEFAULT_PROFILE, name);
+        ini.write_to_file(&self.file_path).with_context(|| {
+            format!(
+                "Failed to write the default profile to '{}'",
+                self.file_path
+            )
+        })
     }
 
-    #[allow(dead_code)]
+    /// Writes `profile` to the file, replacing the section of the same
+    /// name and keeping the other profiles.
     pub fn put_profile(&self, profile: &IniProfile) -> Result<()> {
-        let mut ini = Ini::new();
+        let mut ini = self.load_or_create()?;
+        ini.delete(Some(profile.name.clone()));
         let mut section = ini.with_section(Some(profile.name.clone()));
 
         if profile.server().is_some() {
@@ -195,11 +316,71 @@ impl IniProfileStore {
         if profile.password().is_some() {
             section.set(INI_PASSWORD, profile.password().unwrap());
         }
-        section.set(INI_INSECURE, profile.insecure().unwrap().to_string());
+        if let Some(insecure) = profile.insecure() {
+            section.set(INI_INSECURE, insecure.to_string());
+        }
 
         if profile.ca_cert().is_some() {
             section.set(INI_CA_CERT, profile.ca_cert().unwrap());
         }
+        if let Some(proxy) = &profile.proxy {
+            section.set(INI_PROXY, proxy.to_string());
+        }
+        if let Some(proxy_user) = &profile.proxy_user {
+            section.set(INI_PROXY_USER, proxy_user.as_str());
+        }
+        if let Some(proxy_password) = &profile.proxy_password {
+            section.set(INI_PROXY_PASSWORD, proxy_password.as_str());
+        }
+        if let Some(connect_timeout) = profile.policy.connect_timeout {
+            section.set(
+                INI_CONNECT_TIMEOUT,
+                connect_timeout.as_secs_f64().to_string(),
+            );
+        }
+        if let Some(max_time) = profile.policy.max_time {
+            section.set(INI_MAX_TIME, max_time.as_secs_f64().to_string());
+        }
+        if let Some(retries) = profile.policy.retries {
+            section.set(INI_RETRIES, retries.to_string());
+        }
+        if let Some(retry_all_methods) = profile.policy.retry_all_methods {
+            section.set(INI_RETRY_ALL_METHODS, retry_all_methods.to_string());
+        }
+        if let Some(max_redirects) = profile.policy.max_redirects {
+            section.set(INI_MAX_REDIRECTS, max_redirects.to_string());
+        }
+        if let Some(follow) = profile.policy.follow {
+            section.set(INI_FOLLOW, follow.to_string());
+        }
+        if let Some(max_header_size) = profile.policy.max_header_size {
+            section.set(INI_MAX_HEADER_SIZE, max_header_size.to_string());
+        }
+        if let Some(location_trusted) = profile.policy.location_trusted {
+            section.set(INI_LOCATION_TRUSTED, location_trusted.to_string());
+        }
+        if let Some(abort) = profile.policy.abort_on_redirect_loop {
+            section.set(INI_ABORT_ON_REDIRECT_LOOP, abort.to_string());
+        }
+
+        if let Some(oauth2) = profile.oauth2() {
+            section.set(INI_OAUTH_TOKEN_URL, oauth2.token_url.as_str());
+            section.set(INI_OAUTH_CLIENT_ID, oauth2.client_id.as_str());
+            section.set(INI_OAUTH_CLIENT_SECRET, oauth2.client_secret.as_str());
+            if let Some(scope) = &oauth2.scope {
+                section.set(INI_OAUTH_SCOPE, scope.as_str());
+            }
+        }
+
+        if let Some(aws) = profile.aws_sigv4() {
+            section.set(INI_AWS_ACCESS_KEY_ID, aws.access_key_id.as_str());
+            section.set(INI_AWS_SECRET_ACCESS_KEY, aws.secret_access_key.as_str());
+            if let Some(token) = &aws.session_token {
+                section.set(INI_AWS_SESSION_TOKEN, token.as_str());
+            }
+            section.set(INI_AWS_REGION, aws.region.as_str());
+            section.set(INI_AWS_SERVICE, aws.service.as_str());
+        }
 
         for (k, v) in profile.headers.iter() {
             section.set(format!("@{k}"), v);
@@ -214,6 +395,202 @@ impl IniProfileStore {
 
         Ok(())
     }
+
+    fn load_or_create(&self) -> Result<Ini> {
+        let path = std::path::Path::new(&self.file_path);
+        if path.exists() {
+            return Ini::load_from_file(path).with_context(|| {
+                format!(
+                    "Failed to load profile configuration from '{}'",
+                    self.file_path
+                )
+            });
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create '{}'", dir.display()))?;
+        }
+        Ok(Ini::new())
+    }
+}
+
+fn parse_section(name: &str, section: &Properties) -> Result<IniProfile> {
+    let mut headers = HashMap::<String, String>::new();
+    for (key, value) in section.iter() {
+        // here, we'll pick up only ones start with at sign
+        if let Some(stripped) = key.strip_prefix("@") {
+            headers.insert(stripped.to_string().to_lowercase(), value.to_string());
+        }
+    }
+
+    fn try_get<T>(section: &Properties, key: &str) -> Result<Option<T>>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Debug,
+    {
+        match section.get(key) {
+            Some(s) => match s.parse::<T>() {
+                Ok(value) => Ok(Some(value)),
+                Err(e) => Err(anyhow!("Invalid value '{}' for '{}': {:?}", s, key, e)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn try_get_bool(section: &Properties, key: &str) -> Result<Option<bool>> {
+        match section.get(key) {
+            Some(s) => match s.to_lowercase().as_str() {
+                "true" => Ok(Some(true)),
+                "false" => Ok(Some(false)),
+                _ => Err(anyhow!(
+                    "Invalid boolean value '{}' for '{}'. Expected 'true' or 'false'",
+                    s,
+                    key
+                )),
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn try_get_seconds(section: &Properties, key: &str) -> Result<Option<Duration>> {
+        section
+            .get(key)
+            .map(|s| parse_seconds(s).with_context(|| format!("Invalid value for '{key}'")))
+            .transpose()
+    }
+
+    type Values<'a, const N: usize> = Option<[&'a str; N]>;
+
+    // Returns the values of `keys`, which must be all set or none
+    fn try_get_all<'a, const N: usize>(
+        section: &'a Properties,
+        keys: [&str; N],
+    ) -> Result<Values<'a, N>> {
+        let values = keys.map(|k| section.get(k));
+        if values.iter().all(Option::is_none) {
+            return Ok(None);
+        }
+        if values.iter().all(Option::is_some) {
+            return Ok(Some(values.map(Option::unwrap)));
+        }
+        Err(anyhow!(
+            "Missing {}",
+            keys.iter()
+                .zip(values)
+                .filter(|(_, v)| v.is_none())
+                .map(|(k, _)| format!("'{k}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+
+    // The OAuth2 settings are all or nothing, except the scope
+    fn try_get_oauth2(section: &Properties) -> Result<Option<OAuth2Config>> {
+        let keys = [
+            INI_OAUTH_TOKEN_URL,
+            INI_OAUTH_CLIENT_ID,
+            INI_OAUTH_CLIENT_SECRET,
+        ];
+        Ok(
+            try_get_all(section, keys)?.map(|[token_url, client_id, client_secret]| OAuth2Config {
+                token_url: token_url.to_string(),
+                client_id: client_id.to_string(),
+                client_secret: client_secret.to_string(),
+                scope: section.get(INI_OAUTH_SCOPE).map(|s| s.to_string()),
+            }),
+        )
+    }
+
+    // Likewise for the AWS settings, except the session token
+    fn try_get_aws_sigv4(section: &Properties) -> Result<Option<AwsSigV4Config>> {
+        let keys = [
+            INI_AWS_ACCESS_KEY_ID,
+            INI_AWS_SECRET_ACCESS_KEY,
+            INI_AWS_REGION,
+            INI_AWS_SERVICE,
+        ];
+        Ok(
+            try_get_all(section, keys)?.map(
+                |[access_key_id, secret_access_key, region, service]| AwsSigV4Config {
+                    access_key_id: access_key_id.to_string(),
+                    secret_access_key: secret_access_key.to_string(),
+                    session_token: section.get(INI_AWS_SESSION_TOKEN).map(|s| s.to_string()),
+                    region: region.to_string(),
+                    service: service.to_string(),
+                },
+            ),
+        )
+    }
+
+    Ok(IniProfile {
+        name: name.to_string(),
+        server: try_get::<Endpoint>(section, INI_HOST)
+            .with_context(|| format!("Failed to parse host for profile '{name}'"))?,
+        user: try_get(section, INI_USER)?,
+        password: try_get(section, INI_PASSWORD)?,
+        insecure: try_get_bool(section, INI_INSECURE)
+            .with_context(|| format!("Failed to parse insecure flag for profile '{name}'"))?,
+        ca_cert: try_get(section, INI_CA_CERT)?,
+        headers: headers.clone(),
+        proxy: try_get::<Endpoint>(section, INI_PROXY)
+            .with_context(|| format!("Failed to parse proxy for profile '{name}'"))?,
+        proxy_user: try_get(section, INI_PROXY_USER)?,
+        proxy_password: try_get(section, INI_PROXY_PASSWORD)?,
+        policy: ConnectionPolicy {
+            connect_timeout: try_get_seconds(section, INI_CONNECT_TIMEOUT)
+                .with_context(|| format!("Failed to parse connect_timeout for profile '{name}'"))?,
+            max_time: try_get_seconds(section, INI_MAX_TIME)
+                .with_context(|| format!("Failed to parse max_time for profile '{name}'"))?,
+            retries: try_get(section, INI_RETRIES)
+                .with_context(|| format!("Failed to parse retries for profile '{name}'"))?,
+            retry_all_methods: try_get_bool(section, INI_RETRY_ALL_METHODS).with_context(|| {
+                format!("Failed to parse retry_all_methods flag for profile '{name}'")
+            })?,
+            max_redirects: try_get(section, INI_MAX_REDIRECTS)
+                .with_context(|| format!("Failed to parse max_redirects for profile '{name}'"))?,
+            follow: try_get_bool(section, INI_FOLLOW)
+                .with_context(|| format!("Failed to parse follow flag for profile '{name}'"))?,
+            max_header_size: section
+                .get(INI_MAX_HEADER_SIZE)
+                .map(parse_size)
+                .transpose()
+                .with_context(|| format!("Failed to parse max_header_size for profile '{name}'"))?,
+            location_trusted: try_get_bool(section, INI_LOCATION_TRUSTED).with_context(|| {
+                format!("Failed to parse location_trusted flag for profile '{name}'")
+            })?,
+            abort_on_redirect_loop: try_get_bool(section, INI_ABORT_ON_REDIRECT_LOOP)
+                .with_context(|| {
+                    format!("Failed to parse abort_on_redirect_loop flag for profile '{name}'")
+                })?,
+        },
+        oauth2: try_get_oauth2(section)
+            .with_context(|| format!("Failed to parse OAuth2 settings for profile '{name}'"))?,
+        aws_sigv4: try_get_aws_sigv4(section)
+            .with_context(|| format!("Failed to parse AWS settings for profile '{name}'"))?,
+        resolve: vec![],
+        dns_servers: vec![],
+    })
+}
+
+/// Parses a profile given on the command line as `key=value` pairs
+/// separated by semicolons, e.g. `host=https://x;user=u;@accept=text/plain`.
+/// The keys are the ones of the INI file, with headers prefixed by `@`.
+pub fn parse_inline_profile(s: &str) -> Result<IniProfile> {
+    let mut section = Properties::new();
+    for pair in s.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid inline profile entry '{pair}', expected key=value"))?;
+        let key = key.trim();
+        if !key.starts_with('@') && !INLINE_KEYS.contains(&key) {
+            return Err(anyhow!(
+                "Unknown inline profile key '{key}'. Expected one of {} or @header",
+                INLINE_KEYS.join(", ")
+            ));
+        }
+        section.insert(key, value.trim());
+    }
+    parse_section(PROFILE_INLINE, &section)
 }
 
 pub fn get_blank_profile() -> IniProfile {
@@ -226,10 +603,18 @@ pub fn get_blank_profile() -> IniProfile {
         ca_cert: None,
         headers: HashMap::new(),
         proxy: None,
+        proxy_user: None,
+        proxy_password: None,
+        policy: ConnectionPolicy::default(),
+        oauth2: None,
+        aws_sigv4: None,
+        resolve: vec![],
+        dns_servers: vec![],
     }
 }
 
-#[allow(dead_code)]
+/// Asks on the terminal whether to create the missing profile `name`
+/// and for its settings.
 pub fn ask_new_profile(name: &str, i: &std::io::Stdin) -> Result<Option<IniProfile>> {
     let init_msg = format!("Profile \"{name}\" doesn't exist. Do you want to create it? [y/N]: ");
     if !ask_binary(i, &init_msg)? {
@@ -281,6 +666,13 @@ pub fn ask_new_profile(name: &str, i: &std::io::Stdin) -> Result<Option<IniProfi
         ca_cert,
         headers: HashMap::new(),
         proxy: None,
+        proxy_user: None,
+        proxy_password: None,
+        policy: ConnectionPolicy::default(),
+        oauth2: None,
+        aws_sigv4: None,
+        resolve: vec![],
+        dns_servers: vec![],
     }))
 }
 #[cfg(test)]
@@ -382,6 +774,13 @@ mod test {
             ca_cert: Some(TEST_CA_CERT.to_string()),
             headers,
             proxy: None,
+            proxy_user: None,
+            proxy_password: None,
+            policy: ConnectionPolicy::default(),
+            oauth2: None,
+            aws_sigv4: None,
+            resolve: vec![],
+            dns_servers: vec![],
         };
 
         let temp_file = NamedTempFile::new()?;
@@ -394,6 +793,80 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn put_profile_should_keep_other_profiles() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(
+            b"[other]\nhost=https://other.example.com\n\n\
+              [default]\nhost=https://old.example.com\nuser=stale\n",
+        )?;
+        let path = file.path().to_str().unwrap().to_string();
+
+        let mut profile = get_blank_profile();
+        profile.name = DEFAULT_INI_SECTION.to_string();
+        profile.server = Some(Endpoint::parse("https://new.example.com")?);
+        let store = IniProfileStore::new(&path);
+        store.put_profile(&profile)?;
+
+        let other = store.get_profile("other")?.unwrap();
+        assert_eq!(
+            other.server().unwrap().to_string(),
+            "https://other.example.com"
+        );
+        let replaced = store.get_profile(DEFAULT_INI_SECTION)?.unwrap();
+        assert_eq!(
+            replaced.server().unwrap().to_string(),
+            "https://new.example.com"
+        );
+        assert_eq!(replaced.user(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_default_profile_should_persist_across_writes() -> Result<()> {
+        let mut file = NamedTempFile::new()?;
+        file.write_all(b"[staging]\nhost=https://staging.example.com\n")?;
+        let path = file.path().to_str().unwrap().to_string();
+        let store = IniProfileStore::new(&path);
+        assert_eq!(store.get_default_profile()?, None);
+
+        store.set_default_profile("staging")?;
+        assert_eq!(store.get_default_profile()?, Some("staging".to_string()));
+
+        // Writing a profile keeps the pointer, and the pointer does not
+        // show up as a profile
+        let mut profile = get_blank_profile();
+        profile.name = DEFAULT_INI_SECTION.to_string();
+        store.put_profile(&profile)?;
+        assert_eq!(store.get_default_profile()?, Some("staging".to_string()));
+        assert!(store.get_profile("staging")?.is_some());
+
+        let err = store.set_default_profile("missing").unwrap_err();
+        assert!(err.to_string().contains("Profile 'missing' not found"));
+        assert_eq!(
+            IniProfileStore::new("/nonexistent/profile").get_default_profile()?,
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn put_profile_should_create_missing_directory() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("httpc").join("profile");
+
+        let mut profile = get_blank_profile();
+        profile.name = DEFAULT_INI_SECTION.to_string();
+        let store = IniProfileStore::new(path.to_str().unwrap());
+        assert!(!store.exists());
+        store.put_profile(&profile)?;
+
+        assert!(path.exists());
+        assert!(store.exists());
+        Ok(())
+    }
+
     #[derive(Debug)]
     struct TestArgs {
         url: Endpoint,
@@ -402,6 +875,9 @@ mod test {
         ca_cert: String,
         headers: HashMap<String, String>,
         proxy: Option<Endpoint>,
+        proxy_user: Option<String>,
+        proxy_password: Option<String>,
+        policy: ConnectionPolicy,
     }
 
     impl TestArgs {
@@ -419,6 +895,9 @@ mod test {
                 ca_cert: ca_cert.to_string(),
                 headers: headers.clone(),
                 proxy: None,
+                proxy_user: None,
+                proxy_password: None,
+                policy: ConnectionPolicy::default(),
             }
         }
     }
@@ -444,13 +923,25 @@ mod test {
             Some(&self.ca_cert)
         }
 
-        fn headers(&self) -> &HashMap<String, String> {
-            &self.headers
+        fn headers(&self) -> ProfileHeaders<'_> {
+            Cow::Borrowed(&self.headers)
         }
 
         fn proxy(&self) -> Option<&Endpoint> {
             self.proxy.as_ref()
         }
+
+        fn proxy_user(&self) -> Option<&String> {
+            self.proxy_user.as_ref()
+        }
+
+        fn proxy_password(&self) -> Option<&String> {
+            self.proxy_password.as_ref()
+        }
+
+        fn policy(&self) -> ConnectionPolicy {
+            self.policy
+        }
     }
 
     #[test]
@@ -468,6 +959,13 @@ mod test {
             ca_cert: None,
             headers: headers.clone(),
             proxy: None,
+            proxy_user: None,
+            proxy_password: None,
+            policy: ConnectionPolicy::default(),
+            oauth2: None,
+            aws_sigv4: None,
+            resolve: vec![],
+            dns_servers: vec![],
         };
 
         let mut headers: HashMap<String, String> = HashMap::new();
@@ -496,6 +994,331 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn clear_auth_should_remove_credentials_and_authorization_header() -> Result<()> {
+        let mut headers: HashMap<String, String> = HashMap::new();
+        headers.insert("authorization".to_string(), "Bearer token".to_string());
+        headers.insert("content-type".to_string(), "application/json".to_string());
+
+        let mut profile = IniProfile {
+            name: DEFAULT_INI_SECTION.to_string(),
+            server: Some(Endpoint::parse("https://localhost:8081")?),
+            user: Some(TEST_USER.to_string()),
+            password: Some(TEST_PASSWORD.to_string()),
+            insecure: None,
+            ca_cert: None,
+            headers,
+            proxy: None,
+            proxy_user: None,
+            proxy_password: None,
+            policy: ConnectionPolicy::default(),
+            oauth2: None,
+            aws_sigv4: None,
+            resolve: vec![],
+            dns_servers: vec![],
+        };
+
+        profile.clear_auth();
+
+        assert!(profile.user().is_none());
+        assert!(profile.password().is_none());
+        assert_eq!(profile.headers().len(), 1);
+        assert!(profile.headers().contains_key("content-type"));
+        assert!(profile.server().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn clear_headers_should_keep_everything_else() -> Result<()> {
+        let mut headers: HashMap<String, String> = HashMap::new();
+        headers.insert("user-agent".to_string(), "custom/1.0".to_string());
+
+        let mut profile = IniProfile {
+            name: DEFAULT_INI_SECTION.to_string(),
+            server: Some(Endpoint::parse("https://localhost:8081")?),
+            user: Some(TEST_USER.to_string()),
+            password: Some(TEST_PASSWORD.to_string()),
+            insecure: None,
+            ca_cert: None,
+            headers,
+            proxy: None,
+            proxy_user: None,
+            proxy_password: None,
+            policy: ConnectionPolicy::default(),
+            oauth2: None,
+            aws_sigv4: None,
+            resolve: vec![],
+            dns_servers: vec![],
+        };
+
+        profile.clear_headers();
+
+        assert!(profile.headers().is_empty());
+        assert_eq!(profile.user(), Some(&TEST_USER.to_string()));
+        assert!(profile.server().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_profile_should_parse_timeouts() -> Result<()> {
+        let content = format!(
+            "[{DEFAULT_INI_SECTION}]\n\
+             host=https://example.com\n\
+             connect_timeout=2.5\n\
+             max_time=30\n"
+        );
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(content.as_bytes())?;
+        let path = file.path().to_str().unwrap().to_string();
+
+        let profile = IniProfileStore::new(&path)
+            .get_profile(DEFAULT_INI_SECTION)?
+            .unwrap();
+
+        assert_eq!(
+            profile.policy().connect_timeout,
+            Some(Duration::from_millis(2500))
+        );
+        assert_eq!(profile.policy().max_time, Some(Duration::from_secs(30)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_profile_should_parse_connection_policy() -> Result<()> {
+        let content = format!(
+            "[{DEFAULT_INI_SECTION}]\n\
+             host=https://example.com\n\
+             retries=3\n\
+             retry_all_methods=true\n\
+             max_redirects=5\n\
+             follow=false\n\
+             max_header_size=64K\n\
+             location_trusted=true\n\
+             abort_on_redirect_loop=true\n"
+        );
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(content.as_bytes())?;
+        let path = file.path().to_str().unwrap().to_string();
+        let store = IniProfileStore::new(&path);
+
+        let profile = store.get_profile(DEFAULT_INI_SECTION)?.unwrap();
+        assert_eq!(
+            profile.policy(),
+            ConnectionPolicy {
+                retries: Some(3),
+                retry_all_methods: Some(true),
+                max_redirects: Some(5),
+                follow: Some(false),
+                max_header_size: Some(64 * 1024),
+                location_trusted: Some(true),
+                abort_on_redirect_loop: Some(true),
+                ..Default::default()
+            }
+        );
+
+        // Written back as read
+        store.put_profile(&profile)?;
+        let reloaded = store.get_profile(DEFAULT_INI_SECTION)?.unwrap();
+        assert_eq!(reloaded.policy(), profile.policy());
+
+        std::fs::write(&path, "[default]\nretries=-1\n")?;
+        assert!(store.get_profile(DEFAULT_INI_SECTION).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn ini_profile_merge_should_override_policy_fields_one_by_one() -> Result<()> {
+        let mut profile = parse_inline_profile("connect_timeout=2;max_time=30;retries=3")?;
+        let mut args = TestArgs::new(
+            &Endpoint::parse("http://example.com")?,
+            "u",
+            "p",
+            TEST_CA_CERT,
+            &HashMap::new(),
+        );
+        args.policy = ConnectionPolicy {
+            max_time: Some(Duration::from_secs(5)),
+            follow: Some(false),
+            ..Default::default()
+        };
+
+        profile.merge_profile(&args);
+
+        assert_eq!(
+            profile.policy(),
+            ConnectionPolicy {
+                connect_timeout: Some(Duration::from_secs(2)),
+                max_time: Some(Duration::from_secs(5)),
+                retries: Some(3),
+                retry_all_methods: None,
+                max_redirects: None,
+                follow: Some(false),
+                max_header_size: None,
+                location_trusted: None,
+                abort_on_redirect_loop: None,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_profile_should_reject_invalid_timeout() -> Result<()> {
+        let content = format!(
+            "[{DEFAULT_INI_SECTION}]\n\
+             host=https://example.com\n\
+             max_time=soon\n"
+        );
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(content.as_bytes())?;
+        let path = file.path().to_str().unwrap().to_string();
+
+        let result = IniProfileStore::new(&path).get_profile(DEFAULT_INI_SECTION);
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_profile_should_parse_oauth2_settings() -> Result<()> {
+        let content = format!(
+            "[{DEFAULT_INI_SECTION}]\n\
+             host=https://api.example.com\n\
+             oauth_token_url=https://auth.example.com/oauth/token\n\
+             oauth_client_id=my-client\n\
+             oauth_client_secret=s3cret\n"
+        );
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(content.as_bytes())?;
+        let path = file.path().to_str().unwrap().to_string();
+
+        let profile = IniProfileStore::new(&path)
+            .get_profile(DEFAULT_INI_SECTION)?
+            .unwrap();
+
+        assert_eq!(
+            profile.oauth2(),
+            Some(&OAuth2Config {
+                token_url: "https://auth.example.com/oauth/token".to_string(),
+                client_id: "my-client".to_string(),
+                client_secret: "s3cret".to_string(),
+                scope: None,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_profile_should_reject_incomplete_oauth2_settings() -> Result<()> {
+        let content = format!(
+            "[{DEFAULT_INI_SECTION}]\n\
+             host=https://api.example.com\n\
+             oauth_client_id=my-client\n"
+        );
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(content.as_bytes())?;
+        let path = file.path().to_str().unwrap().to_string();
+
+        let err = IniProfileStore::new(&path)
+            .get_profile(DEFAULT_INI_SECTION)
+            .unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "Failed to parse OAuth2 settings for profile 'default': \
+             Missing 'oauth_token_url', 'oauth_client_secret'"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_profile_should_parse_aws_sigv4_settings() -> Result<()> {
+        let content = format!(
+            "[{DEFAULT_INI_SECTION}]\n\
+             host=https://execute-api.eu-west-1.amazonaws.com\n\
+             aws_access_key_id=AKIDEXAMPLE\n\
+             aws_secret_access_key=secret\n\
+             aws_region=eu-west-1\n\
+             aws_service=execute-api\n"
+        );
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(content.as_bytes())?;
+        let path = file.path().to_str().unwrap().to_string();
+
+        let profile = IniProfileStore::new(&path)
+            .get_profile(DEFAULT_INI_SECTION)?
+            .unwrap();
+
+        assert_eq!(
+            profile.aws_sigv4(),
+            Some(&AwsSigV4Config {
+                access_key_id: "AKIDEXAMPLE".to_string(),
+                secret_access_key: "secret".to_string(),
+                session_token: None,
+                region: "eu-west-1".to_string(),
+                service: "execute-api".to_string(),
+            })
+        );
+        assert!(profile.oauth2().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_profile_should_reject_incomplete_aws_sigv4_settings() -> Result<()> {
+        let content = format!(
+            "[{DEFAULT_INI_SECTION}]\n\
+             aws_access_key_id=AKIDEXAMPLE\n\
+             aws_secret_access_key=secret\n\
+             aws_session_token=token\n"
+        );
+
+        let mut file = NamedTempFile::new()?;
+        file.write_all(content.as_bytes())?;
+        let path = file.path().to_str().unwrap().to_string();
+
+        let err = IniProfileStore::new(&path)
+            .get_profile(DEFAULT_INI_SECTION)
+            .unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "Failed to parse AWS settings for profile 'default': \
+             Missing 'aws_region', 'aws_service'"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_bearer_token_should_replace_credentials() {
+        let mut profile = get_blank_profile();
+        profile.user = Some(TEST_USER.to_string());
+        profile.password = Some(TEST_PASSWORD.to_string());
+        profile
+            .headers
+            .insert("authorization".to_string(), "Basic xyz".to_string());
+
+        profile.set_bearer_token("tok-123");
+
+        assert!(profile.user().is_none());
+        assert!(profile.password().is_none());
+        assert_eq!(
+            profile.headers().get("authorization").unwrap(),
+            "Bearer tok-123"
+        );
+    }
+
     #[test]
     fn test_profile_not_found() -> Result<()> {
         let temp_file = create_ini_file()?;
@@ -610,6 +1433,13 @@ mod test {
             ca_cert: Some("/original/cert.pem".to_string()),
             headers: HashMap::new(),
             proxy: None,
+            proxy_user: None,
+            proxy_password: None,
+            policy: ConnectionPolicy::default(),
+            oauth2: None,
+            aws_sigv4: None,
+            resolve: vec![],
+            dns_servers: vec![],
         };
 
         let merging = TestArgs {
@@ -619,10 +1449,13 @@ mod test {
             ca_cert: "should-not-override".to_string(),
             headers: HashMap::new(),
             proxy: None,
+            proxy_user: None,
+            proxy_password: None,
+            policy: ConnectionPolicy::default(),
         };
 
         // Mock the merge to only merge headers (not other fields)
-        original.headers.extend(merging.headers().clone());
+        original.headers.extend(merging.headers().into_owned());
 
         assert_eq!(original.user(), Some(&"original_user".to_string()));
         assert_eq!(original.password(), Some(&"original_pass".to_string()));
@@ -751,4 +1584,72 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_inline_profile() -> Result<()> {
+        let profile = parse_inline_profile(
+            "host=https://example.com:8443; user=u;password=p=1;insecure=true;\
+             @X-Tenant=acme;max_time=30",
+        )?;
+
+        assert_eq!(profile.name, PROFILE_INLINE);
+        assert_eq!(
+            profile.server().unwrap().to_string(),
+            "https://example.com:8443"
+        );
+        assert_eq!(profile.user(), Some(&"u".to_string()));
+        // Only the first '=' separates the key from the value
+        assert_eq!(profile.password(), Some(&"p=1".to_string()));
+        assert_eq!(profile.insecure(), Some(true));
+        assert_eq!(profile.headers().get("x-tenant"), Some(&"acme".to_string()));
+        assert_eq!(profile.policy().max_time, Some(Duration::from_secs(30)));
+        assert!(profile.ca_cert().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_proxy_credentials() -> Result<()> {
+        let mut profile = parse_inline_profile(
+            "proxy=http://proxy.local:3128;proxy_user=alice;proxy_password=s3cret",
+        )?;
+        assert_eq!(profile.proxy_user(), Some(&"alice".to_string()));
+        assert_eq!(profile.proxy_password(), Some(&"s3cret".to_string()));
+
+        // Like the user, a proxy user replaces the password too
+        profile.merge_profile(&parse_inline_profile("proxy_user=bob")?);
+        assert_eq!(profile.proxy_user(), Some(&"bob".to_string()));
+        assert_eq!(profile.proxy_password(), None);
+        assert!(profile.proxy().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_inline_profile_errors() {
+        let err = parse_inline_profile("hots=https://example.com").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unknown inline profile key 'hots'"));
+        assert!(parse_inline_profile("host").is_err());
+        assert!(parse_inline_profile("insecure=yes").is_err());
+        assert!(parse_inline_profile("oauth_client_id=x").is_err());
+    }
+
+    #[test]
+    fn test_merge_inline_profile() -> Result<()> {
+        let mut profile = test_profile(&create_ini_file()?.to_string_lossy())?;
+        let inline = parse_inline_profile("user=other;@accept=text/plain")?;
+        profile.merge_profile(&inline);
+
+        assert_eq!(profile.user(), Some(&"other".to_string()));
+        assert_eq!(profile.password(), None);
+        assert_eq!(
+            profile.headers().get("accept"),
+            Some(&"text/plain".to_string())
+        );
+        assert!(profile.server().is_some());
+
+        Ok(())
+    }
 }
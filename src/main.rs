@@ -1,18 +1,50 @@
+mod check;
 mod cmd;
+mod config;
 mod decoder;
+#[cfg(feature = "dns-server")]
+mod dns;
+mod error;
+mod expect;
+mod grpc_web;
+mod har;
 mod http;
 mod ini;
+mod netrc;
+mod oauth;
+mod paginate;
+mod rate;
+mod sigv4;
+mod sse;
 mod stdio;
+mod template;
+mod timing;
+mod trace;
 mod url;
 mod utils;
 
-use cmd::CommandLineArgs;
-use http::{HttpClient, HttpConnectionProfile, HttpRequestArgs, HttpResponse};
-use ini::{get_blank_profile, IniProfileStore, DEFAULT_INI_FILE_PATH};
-use reqwest::StatusCode;
-use stdio::StdinArgs;
+use anyhow::Context;
+use check::{check_profile, CheckStatus};
+use cmd::{print_completions, CommandLineArgs, PRETTY_JSON, PRETTY_NONE};
+use config::{effective_config, format_config};
+use error::{ErrorReport, HttpStatusError};
+use expect::expect_body_file;
+use grpc_web::{format_frames, is_grpc_web, parse_frames};
+use har::Har;
+use http::{has_header, HttpClient, HttpConnectionProfile, HttpRequestArgs, HttpResponse};
+use ini::{ask_new_profile, get_blank_profile, IniProfile, IniProfileStore, DEFAULT_INI_FILE_PATH};
+use netrc::{Netrc, NetrcEntry, DEFAULT_NETRC_PATH};
+use oauth::{get_access_token, TokenCache, DEFAULT_TOKEN_CACHE_DIR};
+use paginate::{merge_pages, next_page_url};
+use rate::RateLimiter;
+use reqwest::{header::LOCATION, StatusCode};
+use std::collections::HashSet;
+use std::io::{IsTerminal, Write};
+use stdio::{ask_binary, StdinArgs};
+use trace::{request_body, request_head, response_head, WireTrace};
 use tracing_subscriber::{fmt::time::ChronoLocal, EnvFilter};
-use utils::Result;
+use url::Url;
+use utils::{decode_base64_body, header_lines, pipe_through, sorted_header_lines, Result};
 
 #[tracing::instrument]
 #[tokio::main]
@@ -20,7 +52,79 @@ async fn main() -> Result<()> {
     init_tracing_subscriber();
 
     // Load command line arguments
-    let mut cmd_args = CommandLineArgs::parse();
+    let mut cmd_args = CommandLineArgs::parse().unwrap_or_else(|e| e.exit());
+
+    // Print the shell completion script and exit before touching
+    // stdin, the profile or the network.
+    if let Some(shell) = cmd_args.completions() {
+        print_completions(shell);
+        return Ok(());
+    }
+
+    // Record the default profile and exit. Otherwise use the recorded
+    // one unless --profile is given.
+    let ini_store = IniProfileStore::new(DEFAULT_INI_FILE_PATH);
+    if let Some(name) = cmd_args.set_default_profile() {
+        ini_store.set_default_profile(name)?;
+        println!("Default profile set to '{name}'");
+        return Ok(());
+    }
+    if let Some(name) = ini_store.get_default_profile()? {
+        cmd_args.default_profile(name);
+    }
+
+    // Validate the profile and exit, without reading stdin nor sending
+    // anything. The exit code tells whether any check failed.
+    if cmd_args.check_profile() {
+        let results = check_profile(&ini_store, cmd_args.profile());
+        results.iter().for_each(|r| println!("{r}"));
+        if results.iter().any(|r| r.status == CheckStatus::Fail) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Everything past this point may fail. With --error-format json
+    // the failure is reported as a JSON object on stderr instead of
+    // the plain anyhow message.
+    let mut profile = get_blank_profile();
+    match run(&mut cmd_args, &mut profile).await {
+        Ok(true) => Ok(()),
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            report_error(&cmd_args, &profile, &err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn report_error(cmd_args: &CommandLineArgs, profile: &IniProfile, err: &anyhow::Error) {
+    if !cmd_args.json_errors() {
+        eprintln!("Error: {err:?}");
+        return;
+    }
+    let url = Url::new(profile.server().or(cmd_args.server()), cmd_args.url_path());
+    let report = ErrorReport::new(err, Some(url.to_string()))
+        .with_label(cmd_args.label())
+        .with_request_id(cmd_args.request_id());
+    eprintln!("{}", report.to_json());
+}
+
+/// Returns false when some of the requests to multiple URLs failed.
+async fn run(cmd_args: &mut CommandLineArgs, profile: &mut IniProfile) -> Result<bool> {
+    cmd_args.validate_headers()?;
+
+    // Take the request from a HAR file if requested. It replaces the
+    // method and URL given on the command line, while the -H headers
+    // and stdin body still take precedence.
+    if let Some(har_path) = cmd_args.har() {
+        let har = Har::load(har_path)?;
+        if cmd_args.har_list() {
+            print_har_entries(&har);
+            return Ok(true);
+        }
+        cmd_args.merge_har(har.request(cmd_args.har_entry())?);
+    }
 
     // Read user input from stdin and merge it into command line args.
     // This must happen before loading a profile which may use a
@@ -30,58 +134,561 @@ async fn main() -> Result<()> {
     cmd_args.merge_req(&stdin_args);
     tracing::debug!("stdin_args: {:?}", stdin_args);
 
+    cmd_args.check_upload_file()?;
+    cmd_args.render_body_template()?;
+    cmd_args.validate_patch_body()?;
+
+    // Guard against sending a huge body by mistake
+    if !confirm_body_size(cmd_args, &stdin)? {
+        return Ok(false);
+    }
+
     // Load profile from INI file by name specified in --profile argument
     // (default to "default")
-    // If the profile is not found, then use a blank profile.
+    // If the profile is not found and the request has no host to go to,
+    // offer to create it on the terminal. Otherwise use a blank profile.
     let profile_name = cmd_args.profile();
     let ini_store = IniProfileStore::new(DEFAULT_INI_FILE_PATH);
-    let mut profile = ini_store
-        .get_profile(profile_name)?
-        .unwrap_or(get_blank_profile());
+    *profile = match ini_store.get_profile(profile_name)? {
+        Some(profile) => profile,
+        None if needs_host(cmd_args) && stdin.is_terminal() => {
+            // On the first run, say what a profile is before offering one
+            let first_run = !ini_store.exists();
+            if first_run {
+                print_first_run_intro(ini_store.file_path());
+            }
+            match ask_new_profile(profile_name, &stdin)? {
+                Some(new_profile) => {
+                    ini_store.put_profile(&new_profile)?;
+                    new_profile
+                }
+                None => {
+                    if first_run {
+                        eprintln!(
+                            "Give the full URL instead, e.g. httpc GET https://api.example.com/users"
+                        );
+                    }
+                    get_blank_profile()
+                }
+            }
+        }
+        None => get_blank_profile(),
+    };
     tracing::debug!("INI profile: {:?}", profile);
 
+    // Strip the credentials inherited from the profile if requested.
+    // The ones given on the command line are merged back below.
+    if cmd_args.no_auth() {
+        profile.clear_auth();
+    }
+
+    // Likewise drop the default headers from the profile. The -H ones
+    // are merged back below and sent with the request.
+    if cmd_args.no_default_headers() {
+        profile.clear_headers();
+    }
+
+    // The inline settings are given on the command line too, so they
+    // survive --no-auth and --no-default-headers.
+    if let Some(inline) = cmd_args.profile_inline() {
+        profile.merge_profile(inline);
+        tracing::debug!("Profile with inline settings: {:?}", profile);
+    }
+
+    let netrc = match cmd_args.netrc() {
+        true => Netrc::load(DEFAULT_NETRC_PATH)?,
+        false => None,
+    };
+
+    // Print the settings the request would be sent with and exit. This
+    // comes before the OAuth2 token exchange, which sends a request.
+    if cmd_args.show_config() {
+        let mut merged = profile.clone();
+        merged.merge_profile(cmd_args);
+        apply_netrc(&mut merged, cmd_args, netrc.as_ref());
+        print!(
+            "{}",
+            format_config(&effective_config(&merged, profile, cmd_args))
+        );
+        return Ok(true);
+    }
+
+    // Exchange the client credentials in the profile for a bearer
+    // token. An Authorization header given with -H still wins as it is
+    // merged below.
+    if cmd_args.oauth2() {
+        let config = profile.oauth2().cloned().ok_or_else(|| {
+            anyhow::anyhow!(
+                "--auth oauth2 requires oauth_token_url, oauth_client_id and \
+                 oauth_client_secret in profile '{}'",
+                cmd_args.profile()
+            )
+        })?;
+        let cache = TokenCache::new(DEFAULT_TOKEN_CACHE_DIR);
+        let token = get_access_token(profile, &config, &cache).await?;
+        profile.set_bearer_token(&token);
+    }
+
+    // The signature replaces the basic auth. It is computed for each
+    // request when building it.
+    if cmd_args.aws_sigv4() {
+        if profile.aws_sigv4().is_none() {
+            return Err(anyhow::anyhow!(
+                "--auth aws-sigv4 requires aws_access_key_id, aws_secret_access_key, \
+                 aws_region and aws_service in profile '{}'",
+                cmd_args.profile()
+            ));
+        }
+        profile.clear_auth();
+    }
+
+    let trace = cmd_args
+        .trace_ascii()
+        .map(|target| WireTrace::new(target, !cmd_args.no_redact()))
+        .transpose()?;
+
+    if !cmd_args.extra_urls().is_empty() {
+        return Ok(run_many(cmd_args, profile, netrc.as_ref(), trace.as_ref()).await);
+    }
+
     // Merge the command line arguments (e.g. user, password, etc.)
     // to complete the connection profile. Note the server in profile
     // will be overwritten if a scheme and server is specified in
     // the command line URL
-    profile.merge_profile(&cmd_args);
+    profile.merge_profile(cmd_args);
+    apply_netrc(profile, cmd_args, netrc.as_ref());
     tracing::debug!("Merged profile: {:?}", profile);
 
+    send_pages(
+        cmd_args,
+        profile,
+        &new_client(cmd_args, profile)?,
+        trace.as_ref(),
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Uses the `.netrc` credentials of the host unless the command line or
+/// the profile set up some authentication already.
+fn apply_netrc(profile: &mut IniProfile, cmd_args: &CommandLineArgs, netrc: Option<&Netrc>) {
+    if profile.user().is_some()
+        || has_header(profile.headers().keys(), "authorization")
+        || has_header(HttpRequestArgs::headers(cmd_args).keys(), "authorization")
+    {
+        return;
+    }
+    let entry = match (netrc, profile.server()) {
+        (Some(netrc), Some(server)) => netrc.lookup(server.host()),
+        _ => None,
+    };
+    if let Some(NetrcEntry {
+        login: Some(login),
+        password,
+        ..
+    }) = entry.cloned()
+    {
+        tracing::debug!("Using the .netrc credentials of '{login}'");
+        profile.set_credentials(&login, password.as_deref());
+    }
+}
+
+/// Explains what a profile is before offering to create the first one.
+fn print_first_run_intro(path: &str) {
+    eprintln!("No profiles configured yet ({path} does not exist).");
+    eprintln!(
+        "A profile keeps the host, credentials and headers of a server so that \
+         requests only need a path, e.g. httpc GET /users."
+    );
+}
+
+/// Whether the URL is relative and no inline profile gives the host.
+fn needs_host(cmd_args: &CommandLineArgs) -> bool {
+    cmd_args.server().is_none() && cmd_args.profile_inline().and_then(|p| p.server()).is_none()
+}
+
+fn new_client(cmd_args: &CommandLineArgs, profile: &IniProfile) -> Result<HttpClient> {
+    let client = HttpClient::new(profile)?;
+    match profile.aws_sigv4() {
+        Some(config) if cmd_args.aws_sigv4() => Ok(client.with_aws_sigv4(config.clone())),
+        _ => Ok(client),
+    }
+}
+
+/// Sends the same request to each of the URLs in turn, printing a
+/// `==> url <==` line before each response. A failed request does not
+/// stop the remaining ones; it is reported and makes the run fail.
+async fn run_many(
+    cmd_args: &CommandLineArgs,
+    base_profile: &IniProfile,
+    netrc: Option<&Netrc>,
+    trace: Option<&WireTrace>,
+) -> bool {
+    let first = Url::new(cmd_args.server(), cmd_args.url_path());
+    let urls = std::iter::once(first).chain(cmd_args.extra_urls().iter().cloned());
+
+    let limiter = cmd_args.rate().map(RateLimiter::new);
+    let mut client: Option<HttpClient> = None;
+    let mut succeeded = true;
+    for (i, url) in urls.enumerate() {
+        if let Some(limiter) = &limiter {
+            limiter.acquire().await;
+        }
+
+        if i > 0 {
+            println!();
+        }
+        println!("==> {url} <==");
+
+        let mut args = cmd_args.clone();
+        args.set_url(url);
+        let mut profile = base_profile.clone();
+        profile.merge_profile(&args);
+        apply_netrc(&mut profile, &args, netrc);
+
+        // Reuse the connection pool while the server stays the same
+        if client.as_ref().map(|c| c.endpoint()) != profile.server() {
+            client = match new_client(&args, &profile) {
+                Ok(c) => Some(c),
+                Err(err) => {
+                    report_error(&args, &profile, &err);
+                    succeeded = false;
+                    continue;
+                }
+            };
+        }
+
+        match send_pages(&args, &profile, client.as_ref().unwrap(), trace).await {
+            Ok(status) => succeeded &= status.is_success(),
+            Err(err) => {
+                report_error(&args, &profile, &err);
+                succeeded = false;
+            }
+        }
+    }
+
+    succeeded
+}
+
+/// Sends the request and, with --paginate, the requests for the next
+/// pages linked from each response. The pages are printed one after
+/// the other, or merged into one JSON array with --merge-pages.
+/// Paginating stops at a response without a `rel="next"` link, at one
+/// that isn't a success, after --max-pages pages, or when the next
+/// link goes to another server or to a page already fetched.
+async fn send_pages(
+    cmd_args: &CommandLineArgs,
+    profile: &IniProfile,
+    client: &HttpClient,
+    trace: Option<&WireTrace>,
+) -> Result<StatusCode> {
+    if !cmd_args.paginate() {
+        return Ok(send(cmd_args, profile, client, trace).await?.status());
+    }
+
+    let mut args = cmd_args.clone();
+    let mut visited = HashSet::new();
+    let mut pages = vec![];
+    let status = loop {
+        let url = Url::new(Some(client.endpoint()), args.url_path()).to_string();
+        let url = reqwest::Url::parse(&url).with_context(|| format!("Invalid URL '{url}'"))?;
+        visited.insert(url.clone());
+
+        let res = send(&args, profile, client, trace).await?;
+        if cmd_args.merge_pages() && res.status().is_success() {
+            pages.push(res.bytes().to_vec());
+        }
+
+        let next = match next_page_url(res.headers(), &url) {
+            _ if !res.status().is_success() => None,
+            _ if cmd_args.max_pages() == Some(visited.len() as u32) => None,
+            // Only the server of the profile gets its credentials
+            Some(next) if next.origin() != url.origin() => {
+                eprintln!("Warning: not following the next page on another server: {next}");
+                None
+            }
+            Some(next) if visited.contains(&next) => {
+                eprintln!("Warning: not following the next page fetched already: {next}");
+                None
+            }
+            next => next,
+        };
+        match next {
+            Some(next) => args.set_url(Url::parse(next.as_str())),
+            None => break res.status(),
+        };
+    };
+
+    if cmd_args.merge_pages() {
+        let merged = merge_pages(&pages)?;
+        match (cmd_args.pipe(), cmd_args.pretty()) {
+            (Some(command), _) => {
+                let output = pipe_through(command, merged.to_string().as_bytes())?;
+                std::io::stdout().write_all(&output)?;
+            }
+            (None, PRETTY_NONE) => println!("{merged}"),
+            (None, _) => println!("{}", serde_json::to_string_pretty(&merged)?),
+        }
+    }
+    Ok(status)
+}
+
+async fn send(
+    cmd_args: &CommandLineArgs,
+    profile: &IniProfile,
+    client: &HttpClient,
+    trace: Option<&WireTrace>,
+) -> Result<HttpResponse> {
     // Show the connection profile and request details to stderr output
     // if verbose mode is enabled
     if cmd_args.verbose() {
-        print_profile(&profile);
-        print_request(&cmd_args);
+        print_profile(profile);
+        print_request(cmd_args);
     }
 
     // Send the request and print the response
-    let res = HttpClient::new(&profile)?.request(&cmd_args).await?;
+    let req = client
+        .build_request(cmd_args)
+        .context("Failed to build HTTP request")?;
+    if let Some(trace) = trace {
+        trace.request(&req, client.default_headers())?;
+    }
+
+    // Print the parts of the request selected with --print, separated
+    // by a blank line from the next part
+    let print = cmd_args.print();
+    let body = request_body(&req);
+    let mut parts = vec![];
+    if print.request_headers {
+        parts.push(request_head(&req, client.default_headers(), false).join("\n"));
+    }
+    if print.request_body && !body.is_empty() {
+        parts.push(String::from_utf8_lossy(body).to_string());
+    }
+    if !parts.is_empty() {
+        println!("{}\n", parts.join("\n\n"));
+    }
+
+    // The events of a stream are printed as they arrive, unless the
+    // body goes to another command which then gets the whole stream.
+    // So are the response headers, which can only come first.
+    let live = print.response_body
+        && !cmd_args.merge_pages()
+        && !cmd_args.decode_base64_body()
+        && !print.response_headers
+        && cmd_args.pipe().is_none()
+        && !cmd_args.discard_body();
+    let started = std::time::Instant::now();
+    let res = client
+        .execute_streaming(req, cmd_args, |event| {
+            if live {
+                let mut stdout = std::io::stdout();
+                writeln!(stdout, "{}", event.data)?;
+                stdout.flush()?;
+            }
+            Ok(())
+        })
+        .await?;
+    let elapsed = started.elapsed();
     tracing::debug!("Response: {:?}", res);
+    if let Some(trace) = trace {
+        trace.response(&res)?;
+    }
+    if let Some(target) = cmd_args.timing_header() {
+        print_timings(target, &res)?;
+    }
 
     // Print the response details to stderr if verbose mode is enabled
     if cmd_args.verbose() {
-        print_response(&res);
+        print_response(&res, cmd_args);
     }
 
-    print_result(&res);
+    // Non-success responses are failures for scripts consuming the
+    // JSON error report, but for the redirect asked with --show-location
+    if cmd_args.json_errors() && !res.status().is_success() && !shows_location(&res, cmd_args) {
+        let url = Url::new(profile.server(), cmd_args.url_path());
+        return Err(
+            HttpStatusError::new(res.status(), url.to_string(), res.body().to_string()).into(),
+        );
+    }
 
-    Ok(())
+    // A golden test prints the diff instead of the body
+    if let Some(path) = cmd_args.expect_body_file() {
+        expect_body_file(path, &res)?;
+    }
+
+    if cmd_args.discard_body() {
+        println!("{} in {:.3}s", res.status(), elapsed.as_secs_f64());
+        return Ok(res);
+    }
+
+    if print.response_headers {
+        println!("{}", response_head(&res).join("\n"));
+        if !print.response_body {
+            return Ok(res);
+        }
+        println!();
+    }
+
+    // The decoded body replaces the body as received
+    let decoded = match cmd_args.decode_base64_body() && is_printable(res.status()) {
+        true => Some(decode_base64_body(res.bytes())?),
+        false => None,
+    };
+
+    match (cmd_args.pipe(), &decoded) {
+        // Printed all together once the last page is fetched
+        _ if !print.response_body || cmd_args.merge_pages() => {}
+        (Some(command), _) if is_printable(res.status()) => {
+            let output = pipe_through(command, decoded.as_deref().unwrap_or(res.bytes()))?;
+            std::io::stdout().write_all(&output)?;
+        }
+        (_, Some(bytes)) => match std::str::from_utf8(bytes) {
+            Ok(text) => println!("{text}"),
+            Err(_) => write_binary(bytes, &decoded_summary(bytes), cmd_args)?,
+        },
+        _ if live && res.streamed() => {}
+        _ if cmd_args.grpc_web() && is_grpc_web(res.content_type()) => {
+            println!("{}", format_grpc_web(&res, cmd_args.pretty())?)
+        }
+        _ => print_result(&res, cmd_args)?,
+    }
+
+    Ok(res)
 }
 
-fn print_result(res: &HttpResponse) {
+/// Asks whether to send a body larger than --warn-body-size. Without a
+/// terminal to ask on (e.g. the body came from stdin), it is an error
+/// unless --yes is given.
+fn confirm_body_size(cmd_args: &CommandLineArgs, stdin: &std::io::Stdin) -> Result<bool> {
+    let size = cmd_args.body_size()?;
+    let Some(limit) = cmd_args
+        .warn_body_size()
+        .filter(|&limit| size > limit as u64)
+    else {
+        return Ok(true);
+    };
+    if !stdin.is_terminal() {
+        return Err(anyhow::anyhow!(
+            "Request body is {size} bytes, more than --warn-body-size {limit}. \
+             Pass --yes to send it anyway"
+        ));
+    }
+    let send = ask_binary(
+        stdin,
+        &format!(
+            "Request body is {size} bytes, more than --warn-body-size {limit}. Send it? [y/n]: "
+        ),
+    )?;
+    Ok(send)
+}
+
+/// Prints the timings of `res` to stderr, or appends them to the file
+/// `target`.
+fn print_timings(target: &str, res: &HttpResponse) -> Result<()> {
+    let line = res.timings().to_server_timing();
+    if target.is_empty() {
+        eprintln!("{line}");
+        return Ok(());
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(target)
+        .and_then(|mut file| writeln!(file, "{line}"))
+        .with_context(|| format!("Failed to write timings to '{target}'"))
+}
+
+fn print_har_entries(har: &Har) {
+    for (index, request) in har.requests().iter().enumerate() {
+        println!("{index}: {} {}", request.method().unwrap(), request.url());
+    }
+}
+
+/// Whether the body of a response with this status goes to stdout.
+/// 206 is the answer to a --range request carrying the partial content.
+fn is_printable(status: StatusCode) -> bool {
+    matches!(status, StatusCode::OK | StatusCode::PARTIAL_CONTENT)
+}
+
+/// Whether the Location of `res` is printed instead of its body.
+fn shows_location(res: &HttpResponse, cmd_args: &CommandLineArgs) -> bool {
+    cmd_args.show_location() && res.status().is_redirection()
+}
+
+fn print_result(res: &HttpResponse, cmd_args: &CommandLineArgs) -> Result<()> {
+    // Print where the redirect goes, as received
+    if shows_location(res, cmd_args) {
+        let location = res.header(LOCATION.as_str()).ok_or_else(|| {
+            anyhow::anyhow!("{}: the redirect has no Location header", res.status())
+        })?;
+        println!("{location}");
+        return Ok(());
+    }
+
     // Print the response body
-    if res.status() == StatusCode::OK {
-        if res.json().is_some() {
-            println!(
-                "{}",
-                serde_json::to_string_pretty(res.json().as_ref().unwrap()).unwrap()
-            );
+    if is_printable(res.status()) {
+        if res.is_binary() {
+            write_binary(res.bytes(), &binary_summary(res), cmd_args)?;
         } else {
-            println!("{}", res.body());
+            println!("{}", format_body(res, cmd_args.pretty()));
         }
+    } else if res.is_binary() {
+        eprintln!("{}: {}", res.status(), binary_summary(res));
     } else {
         eprintln!("{}: {}", res.status(), res.body());
     }
+    Ok(())
+}
+
+/// Writes a binary body to stdout. Raw bytes would garble the terminal,
+/// so only `summary` is printed unless stdout is redirected or
+/// --force-binary-output is given.
+fn write_binary(bytes: &[u8], summary: &str, cmd_args: &CommandLineArgs) -> Result<()> {
+    if cmd_args.force_binary_output() || !std::io::stdout().is_terminal() {
+        std::io::stdout().write_all(bytes)?;
+    } else {
+        println!("{summary}");
+        eprintln!("Use --force-binary-output or redirect stdout to a file to get the body");
+    }
+    Ok(())
+}
+
+/// The body pretty-printed as `--pretty` says. A body that isn't JSON
+/// after all is left as is.
+fn format_body(res: &HttpResponse, pretty: &str) -> String {
+    let json = match pretty {
+        PRETTY_NONE => None,
+        PRETTY_JSON => serde_json::from_str(res.body()).ok(),
+        _ => res.json().cloned(),
+    };
+    match json {
+        Some(json) => serde_json::to_string_pretty(&json).unwrap(),
+        None => res.body().to_string(),
+    }
+}
+
+/// The frames of a gRPC-Web response, which answers 200 even to a failed
+/// call, the status of the call being in the trailers.
+fn format_grpc_web(res: &HttpResponse, pretty: &str) -> Result<String> {
+    let frames = parse_frames(res.bytes())?;
+    let json = res
+        .content_type()
+        .is_some_and(|c| c.to_lowercase().contains("+json"));
+    Ok(format_frames(&frames, json, pretty != PRETTY_NONE))
+}
+
+fn binary_summary(res: &HttpResponse) -> String {
+    let content_type = res.content_type().unwrap_or("unknown");
+    format!(
+        "[binary data, {} bytes, content-type {content_type}]",
+        res.bytes().len()
+    )
+}
+
+fn decoded_summary(bytes: &[u8]) -> String {
+    format!("[binary data, {} bytes, decoded from base64]", bytes.len())
 }
 
 fn init_tracing_subscriber() {
@@ -159,34 +766,100 @@ fn print_profile(profile: &impl HttpConnectionProfile) {
     if profile.proxy().is_some() {
         eprintln!(">   proxy: {}", profile.proxy().unwrap());
     }
+
+    if let Some(proxy_user) = profile.proxy_user() {
+        eprintln!(">   proxy-user: {proxy_user}");
+    }
+
+    let policy = profile.policy();
+    if let Some(connect_timeout) = policy.connect_timeout {
+        eprintln!(">   connect-timeout: {connect_timeout:?}");
+    }
+
+    if let Some(max_time) = policy.max_time {
+        eprintln!(">   max-time: {max_time:?}");
+    }
+
+    if let Some(retries) = policy.retries {
+        eprintln!(">   retries: {retries}");
+    }
+
+    if let Some(retry_all_methods) = policy.retry_all_methods {
+        eprintln!(">   retry-all-methods: {retry_all_methods}");
+    }
+
+    if let Some(max_redirects) = policy.max_redirects {
+        eprintln!(">   max-redirects: {max_redirects}");
+    }
+
+    if let Some(follow) = policy.follow {
+        eprintln!(">   follow: {follow}");
+    }
+
+    if let Some(max_header_size) = policy.max_header_size {
+        eprintln!(">   max-header-size: {max_header_size}");
+    }
+
+    if let Some(location_trusted) = policy.location_trusted {
+        eprintln!(">   location-trusted: {location_trusted}");
+    }
+
+    if let Some(abort) = policy.abort_on_redirect_loop {
+        eprintln!(">   abort-on-redirect-loop: {abort}");
+    }
+
+    for entry in profile.resolve() {
+        eprintln!(
+            ">   resolve: {}:{} -> {:?}",
+            entry.host, entry.port, entry.addrs
+        );
+    }
+    if !profile.dns_servers().is_empty() {
+        eprintln!(">   dns-servers: {:?}", profile.dns_servers());
+    }
 }
 
 #[tracing::instrument]
-fn print_request(req: &impl HttpRequestArgs) {
+fn print_request(req: &CommandLineArgs) {
     let url = req
         .url_path()
         .map(|u| u.to_string())
         .unwrap_or("<none>".to_string());
     eprintln!("> request:");
+    if let Some(label) = req.label() {
+        eprintln!(">   label: {label}");
+    }
+    if let Some(request_id) = req.request_id() {
+        eprintln!(">   request-id: {request_id}");
+    }
     eprintln!(">   method: {}", req.method().unwrap());
     eprintln!(">   path: {url}");
     eprintln!(
         ">   body: {}",
-        req.body()
-            .map(|b| if b.len() > 78 {
-                format!("{}...", &b[0..75])
-            } else {
-                b.to_string()
-            })
-            .unwrap_or("<none>".to_string())
+        match (req.body(), req.raw_body()) {
+            (Some(b), _) if b.len() > 78 => format!("{}...", &b[0..75]),
+            (Some(b), _) => b.to_string(),
+            (None, Some(bytes)) => format!("<{} bytes>", bytes.len()),
+            (None, None) => "<none>".to_string(),
+        }
     );
+    // The HTTP client sends the body right after the headers and drops
+    // interim responses, so a 100 Continue is never seen here.
+    if let Some(expect) = HttpRequestArgs::headers(req).get("expect") {
+        eprintln!(">   expect: {expect} (body is sent without waiting for 100 Continue)");
+    }
 }
 
-fn print_response(res: &HttpResponse) {
+fn print_response(res: &HttpResponse, cmd_args: &CommandLineArgs) {
     eprintln!("> response:");
     eprintln!(">   status: {}", res.status());
+    if let Some(charset) = res.detected_charset() {
+        eprintln!(">   charset: {charset} (detected)");
+    }
     eprintln!(">   headers:");
-    res.headers().iter().for_each(|(name, value)| {
-        eprintln!(">     {}: {}", name, value.to_str().unwrap());
-    });
+    let lines = match cmd_args.sort_headers() {
+        true => sorted_header_lines(res.headers(), cmd_args.number_headers()),
+        false => header_lines(res.headers(), cmd_args.number_headers()),
+    };
+    lines.iter().for_each(|line| eprintln!(">     {line}"));
 }
@@ -0,0 +1,174 @@
+use anyhow::Context;
+
+use crate::utils::Result;
+
+pub const DEFAULT_NETRC_PATH: &str = "~/.netrc";
+
+/// Credentials of a `machine` entry, or of the `default` one when
+/// `machine` is None.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetrcEntry {
+    pub machine: Option<String>,
+    pub login: Option<String>,
+    pub password: Option<String>,
+}
+
+/// The credentials in a `.netrc` file, in the format used by ftp and
+/// curl. `macdef` macros and `account` are read but ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Netrc {
+    entries: Vec<NetrcEntry>,
+}
+
+impl Netrc {
+    /// Loads the file at `path`, if it exists. A file readable by other
+    /// users is still used, with a warning as it holds passwords.
+    pub fn load(path: &str) -> Result<Option<Self>> {
+        let path = shellexpand::tilde(path).to_string();
+        if !std::path::Path::new(&path).exists() {
+            return Ok(None);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path)?.permissions().mode();
+            if mode & 0o077 != 0 {
+                eprintln!(
+                    "Warning: '{path}' is accessible by other users, \
+                     consider running 'chmod 600 {path}'"
+                );
+            }
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read netrc file '{path}'"))?;
+        Ok(Some(Self::parse(&content)))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut netrc = Self::default();
+        let mut current: Option<NetrcEntry> = None;
+        let mut lines = content.lines();
+
+        while let Some(line) = lines.next() {
+            let mut tokens = line.split_whitespace();
+            while let Some(token) = tokens.next() {
+                match token {
+                    "machine" | "default" => {
+                        netrc.entries.extend(current.take());
+                        current = Some(NetrcEntry {
+                            machine: match token {
+                                "machine" => tokens.next().map(|s| s.to_string()),
+                                _ => None,
+                            },
+                            login: None,
+                            password: None,
+                        });
+                    }
+                    "login" => {
+                        if let Some(entry) = current.as_mut() {
+                            entry.login = tokens.next().map(|s| s.to_string());
+                        }
+                    }
+                    "password" => {
+                        if let Some(entry) = current.as_mut() {
+                            entry.password = tokens.next().map(|s| s.to_string());
+                        }
+                    }
+                    "account" => {
+                        tokens.next();
+                    }
+                    // A macro runs until the next blank line
+                    "macdef" => {
+                        for line in lines.by_ref() {
+                            if line.trim().is_empty() {
+                                break;
+                            }
+                        }
+                        break;
+                    }
+                    _ if token.starts_with('#') => break,
+                    _ => {}
+                }
+            }
+        }
+        netrc.entries.extend(current);
+        netrc
+    }
+
+    /// Returns the credentials of the first `machine` matching `host`,
+    /// or else the `default` ones.
+    pub fn lookup(&self, host: &str) -> Option<&NetrcEntry> {
+        self.entries
+            .iter()
+            .find(|e| {
+                e.machine
+                    .as_ref()
+                    .is_some_and(|m| m.eq_ignore_ascii_case(host))
+            })
+            .or_else(|| self.entries.iter().find(|e| e.machine.is_none()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const TEST_NETRC: &str = "\
+        # personal credentials\n\
+        machine api.example.com login alice password s3cret\n\
+        machine ftp.example.com\n\
+          login bob\n\
+          account ignored\n\
+          password hunter2\n\
+        macdef init\n\
+        cd /pub\n\
+        machine inside.macro login nobody\n\
+        \n\
+        default login anonymous password guest\n";
+
+    #[test]
+    fn test_parse_and_lookup() {
+        let netrc = Netrc::parse(TEST_NETRC);
+
+        assert_eq!(
+            netrc.lookup("api.example.com"),
+            Some(&NetrcEntry {
+                machine: Some("api.example.com".to_string()),
+                login: Some("alice".to_string()),
+                password: Some("s3cret".to_string()),
+            })
+        );
+        let ftp = netrc.lookup("FTP.example.com").unwrap();
+        assert_eq!(ftp.login, Some("bob".to_string()));
+        assert_eq!(ftp.password, Some("hunter2".to_string()));
+
+        // Unknown hosts and the lines of a macro fall back to the default
+        for host in ["other.example.com", "inside.macro"] {
+            assert_eq!(
+                netrc.lookup(host).unwrap().login,
+                Some("anonymous".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_lookup_without_default() {
+        let netrc = Netrc::parse("machine a.example.com login a");
+        assert!(netrc.lookup("b.example.com").is_none());
+        assert_eq!(netrc.lookup("a.example.com").unwrap().password, None);
+    }
+
+    #[test]
+    fn test_load() {
+        assert_eq!(Netrc::load("/nonexistent/.netrc").unwrap(), None);
+
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(TEST_NETRC.as_bytes()).unwrap();
+        let netrc = Netrc::load(file.path().to_str().unwrap()).unwrap().unwrap();
+        assert!(netrc.lookup("api.example.com").is_some());
+    }
+}
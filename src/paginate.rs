@@ -0,0 +1,120 @@
+use anyhow::anyhow;
+use reqwest::header::{HeaderMap, LINK};
+use serde_json::Value;
+
+use crate::utils::Result;
+
+/// The URL of the next page given by the `Link` headers, resolved
+/// against `base`, the URL of the page they came with.
+/// See https://www.rfc-editor.org/rfc/rfc8288
+pub fn next_page_url(headers: &HeaderMap, base: &reqwest::Url) -> Option<reqwest::Url> {
+    headers
+        .get_all(LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .find_map(find_next_link)
+        .and_then(|link| base.join(link).ok())
+}
+
+// A header holds comma separated links like
+// `<https://api.example.com/items?page=2>; rel="next", <...>; rel="last"`.
+// The relation can list several types, e.g. `rel="next nofollow"`.
+fn find_next_link(value: &str) -> Option<&str> {
+    value.split('<').skip(1).find_map(|link| {
+        let (target, params) = link.split_once('>')?;
+        let is_next = params.split(';').any(|param| {
+            let Some((name, rel)) = param.split_once('=') else {
+                return false;
+            };
+            name.trim().eq_ignore_ascii_case("rel")
+                && rel
+                    .trim()
+                    .trim_end_matches(',')
+                    .trim_matches('"')
+                    .split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("next"))
+        });
+        is_next.then_some(target.trim())
+    })
+}
+
+/// Concatenates the pages, each being a JSON array, into one array.
+pub fn merge_pages(pages: &[impl AsRef<[u8]>]) -> Result<Value> {
+    let mut merged = Vec::new();
+    for (i, page) in pages.iter().enumerate() {
+        match serde_json::from_slice(page.as_ref()) {
+            Ok(Value::Array(items)) => merged.extend(items),
+            _ => {
+                return Err(anyhow!(
+                    "Page {} is not a JSON array, it cannot be merged with --merge-pages",
+                    i + 1
+                ))
+            }
+        }
+    }
+    Ok(Value::Array(merged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn base() -> reqwest::Url {
+        reqwest::Url::parse("https://api.example.com/v1/items?page=1").unwrap()
+    }
+
+    fn links(values: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(LINK, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_next_page_url() {
+        let headers = links(
+            &["<https://api.example.com/v1/items?page=1>; rel=\"prev\", \
+             <https://api.example.com/v1/items?page=3,4>; rel=\"next\", \
+             <https://api.example.com/v1/items?page=9>; rel=\"last\""],
+        );
+        assert_eq!(
+            next_page_url(&headers, &base()).unwrap().as_str(),
+            "https://api.example.com/v1/items?page=3,4"
+        );
+
+        // Relative, unquoted, in another header, with several relations
+        let headers = links(&["</v1/items?page=9>; rel=last", "<?page=2>; REL=next"]);
+        assert_eq!(
+            next_page_url(&headers, &base()).unwrap().as_str(),
+            "https://api.example.com/v1/items?page=2"
+        );
+        let headers = links(&["<items?page=2>; title=\"a\"; rel=\"nofollow next\""]);
+        assert_eq!(
+            next_page_url(&headers, &base()).unwrap().as_str(),
+            "https://api.example.com/v1/items?page=2"
+        );
+    }
+
+    #[test]
+    fn test_no_next_page() {
+        assert_eq!(next_page_url(&HeaderMap::new(), &base()), None);
+        let headers = links(&["<https://api.example.com/v1/items?page=1>; rel=\"prev\""]);
+        assert_eq!(next_page_url(&headers, &base()), None);
+        let headers = links(&["<https://api.example.com/next>; rel=\"nextpage\""]);
+        assert_eq!(next_page_url(&headers, &base()), None);
+    }
+
+    #[test]
+    fn test_merge_pages() {
+        let merged = merge_pages(&["[1, 2]", "[]", "[{\"a\": 3}]"]).unwrap();
+        assert_eq!(merged, serde_json::json!([1, 2, {"a": 3}]));
+
+        let err = merge_pages(&["[1]", "{\"items\": []}"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Page 2 is not a JSON array, it cannot be merged with --merge-pages"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::utils::Result;
+
+/// Parses a number of requests per second (fractions allowed, e.g. `0.5`
+/// for one request every two seconds).
+pub fn parse_rate(s: &str) -> Result<f64> {
+    let rate = s
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Invalid rate '{s}'"))?;
+    if rate.is_finite() && rate > 0.0 {
+        Ok(rate)
+    } else {
+        Err(anyhow::anyhow!("Rate must be greater than 0: '{s}'"))
+    }
+}
+
+/// Spaces requests out at a fixed interval. It can be shared by
+/// concurrent tasks, each of which calls `acquire` before sending.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64) -> Self {
+        Self {
+            interval: Duration::from_secs_f64(1.0 / requests_per_second),
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Waits until the next request may be sent. The first call
+    /// returns immediately.
+    pub async fn acquire(&self) {
+        let mut next = self.next.lock().await;
+        let now = Instant::now();
+        let at = match *next {
+            Some(at) if at > now => at,
+            _ => now,
+        };
+        *next = Some(at + self.interval);
+        // Hold the lock while sleeping so that the slots are taken in order
+        tokio::time::sleep_until(at).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("10").unwrap(), 10.0);
+        assert_eq!(parse_rate(" 0.5 ").unwrap(), 0.5);
+        for input in ["", "fast", "0", "-1", "NaN", "inf"] {
+            assert!(parse_rate(input).is_err(), "Expected error for: {input}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_acquire_spaces_requests() {
+        let limiter = RateLimiter::new(4.0);
+        let started = Instant::now();
+
+        limiter.acquire().await;
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(started.elapsed(), Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_acquire_does_not_accumulate_idle_time() {
+        let limiter = RateLimiter::new(1.0);
+        limiter.acquire().await;
+
+        // Idling longer than the interval must not allow a burst
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        let resumed = Instant::now();
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(resumed.elapsed(), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,300 @@
+use chrono::{DateTime, Utc};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::header::{HeaderValue, AUTHORIZATION, HOST};
+use reqwest::Request;
+use ring::{digest, hmac};
+
+use crate::utils::Result;
+
+pub const AUTH_AWS_SIGV4: &str = "aws-sigv4";
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+const SERVICE_S3: &str = "s3";
+
+// Added by the HTTP client or a proxy on the way, so they can't be
+// part of the signature.
+const UNSIGNED_HEADERS: [&str; 4] = ["authorization", "user-agent", "expect", "connection"];
+
+// Everything but the unreserved characters of RFC 3986
+const URI_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// AWS credentials configured in the profile with the `aws_*` keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AwsSigV4Config {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+    pub region: String,
+    pub service: String,
+}
+
+/// Signs `req` with AWS Signature Version 4, adding the `x-amz-date`
+/// and `Authorization` headers (and `x-amz-security-token` for
+/// temporary credentials). The request must not change afterwards.
+/// See https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv.html
+pub fn sign(req: &mut Request, config: &AwsSigV4Config, now: DateTime<Utc>) -> Result<()> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = match req.body() {
+        Some(body) => match body.as_bytes() {
+            Some(bytes) => sha256_hex(bytes),
+            None => UNSIGNED_PAYLOAD.to_string(),
+        },
+        None => sha256_hex(b""),
+    };
+
+    let headers = req.headers_mut();
+    headers.insert("x-amz-date", HeaderValue::from_str(&amz_date)?);
+    if let Some(token) = &config.session_token {
+        headers.insert("x-amz-security-token", HeaderValue::from_str(token)?);
+    }
+    // S3 wants the payload hash in a header too
+    if config.service == SERVICE_S3 {
+        headers.insert(
+            "x-amz-content-sha256",
+            HeaderValue::from_str(&payload_hash)?,
+        );
+    }
+
+    let canonical = canonical_request(req, &config.service, &payload_hash)?;
+    let scope = format!("{date}/{}/{}/aws4_request", config.region, config.service);
+    let string_to_sign = format!(
+        "{ALGORITHM}\n{amz_date}\n{scope}\n{}",
+        sha256_hex(canonical.request.as_bytes())
+    );
+
+    let mut key = format!("AWS4{}", config.secret_access_key).into_bytes();
+    for part in [&date, &config.region, &config.service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    let authorization = format!(
+        "{ALGORITHM} Credential={}/{scope}, SignedHeaders={}, Signature={signature}",
+        config.access_key_id, canonical.signed_headers
+    );
+    req.headers_mut()
+        .insert(AUTHORIZATION, HeaderValue::from_str(&authorization)?);
+    Ok(())
+}
+
+struct CanonicalRequest {
+    request: String,
+    signed_headers: String,
+}
+
+fn canonical_request(req: &Request, service: &str, payload_hash: &str) -> Result<CanonicalRequest> {
+    let url = req.url();
+
+    // S3 signs the object key as is, the other services sign the path
+    // as sent, i.e. encoded twice.
+    let uri = url
+        .path()
+        .split('/')
+        .map(|segment| match service {
+            SERVICE_S3 => percent_decode_str(segment).decode_utf8_lossy().to_string(),
+            _ => segment.to_string(),
+        })
+        .map(|segment| utf8_percent_encode(&segment, URI_ENCODE).to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let mut query = url
+        .query_pairs()
+        .map(|(k, v)| {
+            (
+                utf8_percent_encode(&k, URI_ENCODE).to_string(),
+                utf8_percent_encode(&v, URI_ENCODE).to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    query.sort();
+    let query = query
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(anyhow::anyhow!("Cannot sign a request without a host")),
+    };
+    let mut headers = Vec::new();
+    // The Host header, when given, replaces the one of the URL
+    if !req.headers().contains_key(HOST) {
+        headers.push((HOST.to_string(), host));
+    }
+    for name in req.headers().keys() {
+        if UNSIGNED_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        let values = req
+            .headers()
+            .get_all(name)
+            .iter()
+            .map(|v| Ok(collapse_spaces(v.to_str()?)))
+            .collect::<Result<Vec<_>>>()?;
+        headers.push((name.to_string(), values.join(",")));
+    }
+    headers.sort();
+
+    let canonical_headers = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}\n"))
+        .collect::<String>();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    Ok(CanonicalRequest {
+        request: format!(
+            "{}\n{uri}\n{query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+            req.method()
+        ),
+        signed_headers,
+    })
+}
+
+fn collapse_spaces(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex(digest::digest(&digest::SHA256, data).as_ref())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use reqwest::Client;
+
+    // The credentials and date of the AWS SigV4 test suite
+    fn test_config() -> AwsSigV4Config {
+        AwsSigV4Config {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+            region: "us-east-1".to_string(),
+            service: "service".to_string(),
+        }
+    }
+
+    fn test_date() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap()
+    }
+
+    fn authorization(req: &Request) -> &str {
+        req.headers()[AUTHORIZATION].to_str().unwrap()
+    }
+
+    #[test]
+    fn test_get_vanilla() {
+        let mut req = Client::new()
+            .get("https://example.amazonaws.com/")
+            .build()
+            .unwrap();
+        sign(&mut req, &test_config(), test_date()).unwrap();
+
+        let canonical = canonical_request(&req, "service", &sha256_hex(b"")).unwrap();
+        assert_eq!(
+            canonical.request,
+            "GET\n/\n\n\
+             host:example.amazonaws.com\n\
+             x-amz-date:20150830T123600Z\n\n\
+             host;x-amz-date\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256_hex(canonical.request.as_bytes()),
+            "bb579772317eb040ac9ed261061d46c1f17a8133879d6129b6e1c25292927e63"
+        );
+        assert_eq!(req.headers()["x-amz-date"], "20150830T123600Z");
+        assert_eq!(
+            authorization(&req),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn test_get_vanilla_query_order_key_case() {
+        let mut req = Client::new()
+            .get("https://example.amazonaws.com/?Param2=value2&Param1=value1")
+            .build()
+            .unwrap();
+        sign(&mut req, &test_config(), test_date()).unwrap();
+
+        assert!(authorization(&req).ends_with(
+            "Signature=b97d918cfa904a5beff61c982a1b6f458b799221646efd99d3219ec94cdf2500"
+        ));
+    }
+
+    #[test]
+    fn test_canonical_headers_and_path() {
+        let req = Client::new()
+            .post("http://localhost:9000/my%20bucket/a b.txt?b=2&a=x%2Fy")
+            .header("X-Custom", "  a   b  ")
+            .header("x-custom", "c")
+            .header("user-agent", "httpc")
+            .body("hello")
+            .build()
+            .unwrap();
+
+        let canonical = canonical_request(&req, "service", "hash").unwrap();
+        assert_eq!(
+            canonical.request,
+            "POST\n/my%2520bucket/a%2520b.txt\na=x%2Fy&b=2\n\
+             host:localhost:9000\n\
+             x-custom:a b,c\n\n\
+             host;x-custom\nhash"
+        );
+        assert_eq!(canonical.signed_headers, "host;x-custom");
+
+        let canonical = canonical_request(&req, SERVICE_S3, "hash").unwrap();
+        assert!(canonical
+            .request
+            .starts_with("POST\n/my%20bucket/a%20b.txt\n"));
+    }
+
+    #[test]
+    fn test_sign_s3_with_session_token() {
+        let mut config = test_config();
+        config.service = SERVICE_S3.to_string();
+        config.session_token = Some("token".to_string());
+        let mut req = Client::new()
+            .put("https://bucket.s3.amazonaws.com/key")
+            .body("hello")
+            .build()
+            .unwrap();
+        sign(&mut req, &config, test_date()).unwrap();
+
+        assert_eq!(
+            req.headers()["x-amz-content-sha256"],
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(req.headers()["x-amz-security-token"], "token");
+        assert!(authorization(&req).contains(
+            "/20150830/us-east-1/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date;x-amz-security-token, "
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+/// A Server-Sent Event. Only the fields a client needs to show the
+/// stream are kept, `retry` is ignored.
+/// See https://html.spec.whatwg.org/multipage/server-sent-events.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+    pub id: Option<String>,
+}
+
+/// Splits a `text/event-stream` body into events as the chunks come in.
+/// A chunk may end in the middle of a line (or of a UTF-8 character),
+/// so the incomplete tail is kept until the next chunk.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    pending: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+    id: Option<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the events completed by `chunk`, i.e. the ones followed
+    /// by a blank line.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.pending.extend_from_slice(chunk);
+        let mut events = vec![];
+        while let Some(pos) = self.pending.iter().position(|&b| b == b'\n') {
+            let line = self.pending.drain(..=pos).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches('\n').trim_end_matches('\r');
+            if let Some(event) = self.process_line(line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        // Lines starting with a colon are comments, often sent as
+        // keep-alives
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            "id" => self.id = Some(value.to_string()),
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        Some(SseEvent {
+            event,
+            data: std::mem::take(&mut self.data).join("\n"),
+            id: self.id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let mut parser = SseParser::new();
+        let events = parser.feed(
+            b": keep-alive\n\
+              data: first\n\n\
+              event: update\r\n\
+              id: 2\r\n\
+              data: {\"a\":1}\r\n\
+              data:line two\r\n\r\n",
+        );
+
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: None,
+                    data: "first".to_string(),
+                    id: None,
+                },
+                SseEvent {
+                    event: Some("update".to_string()),
+                    data: "{\"a\":1}\nline two".to_string(),
+                    id: Some("2".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_events_split_across_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"data: caf").is_empty());
+        // "é" split in the middle of its UTF-8 encoding
+        assert!(parser.feed(&[0xc3]).is_empty());
+        assert!(parser.feed(&[0xa9, b'\n']).is_empty());
+
+        let events = parser.feed(b"\ndata: next");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "café");
+    }
+
+    #[test]
+    fn test_blank_lines_without_data_are_ignored() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"event: ping\n\n\n").is_empty());
+
+        // The event name does not leak into the next event
+        let events = parser.feed(b"data: x\n\n");
+        assert_eq!(events[0].event, None);
+    }
+}
@@ -1,16 +1,15 @@
 use regex::Regex;
 
-use crate::http::HttpRequestArgs;
+use crate::http::{HttpRequestArgs, RequestHeaders};
 use crate::url::UrlPath;
 use crate::utils::Result;
-use std::collections::HashMap;
 use std::io::{IsTerminal, Read, Stdin};
 use std::path::Path;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StdinArgs {
     input: Option<String>,
-    headers: HashMap<String, String>,
+    headers: RequestHeaders,
 }
 
 #[allow(dead_code)]
@@ -19,16 +18,23 @@ impl StdinArgs {
         if std::io::stdin().is_terminal() {
             return Ok(Self {
                 input: None,
-                headers: HashMap::new(),
+                headers: RequestHeaders::new(),
             });
         }
 
+        Self::from_reader(i)
+    }
+
+    /// Reads the whole input as the request body. An empty input (e.g.
+    /// `< /dev/null` or a closed pipe in a script) yields no body so that
+    /// it does not wipe out the body given on the command line.
+    fn from_reader(r: &mut impl Read) -> Result<Self> {
         let mut input = String::new();
-        i.read_to_string(&mut input)?;
+        r.read_to_string(&mut input)?;
 
         Ok(Self {
-            input: Some(input),
-            headers: HashMap::new(),
+            input: (!input.is_empty()).then_some(input),
+            headers: RequestHeaders::new(),
         })
     }
 }
@@ -46,9 +52,36 @@ impl HttpRequestArgs for StdinArgs {
         self.input.as_ref()
     }
 
-    fn headers(&self) -> &HashMap<String, String> {
+    fn headers(&self) -> &RequestHeaders {
         &self.headers
     }
+
+    fn compression(&self) -> Option<&String> {
+        None
+    }
+
+    fn compression_level(&self) -> Option<u32> {
+        None
+    }
+
+    fn decode_as(&self) -> Option<&String> {
+        None
+    }
+
+    fn charset(&self) -> Option<&String> {
+        None
+    }
+    fn discard_body(&self) -> bool {
+        false
+    }
+
+    fn no_decompress(&self) -> bool {
+        false
+    }
+
+    fn normalize_path(&self) -> bool {
+        true
+    }
 }
 
 pub fn ask<T>(i: &Stdin, msg: &str, acceptable: &str) -> Result<T>
@@ -118,11 +151,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_stdin_args_from_reader() {
+        let args = StdinArgs::from_reader(&mut "{\"query\":{}}".as_bytes()).unwrap();
+        assert_eq!(args.body(), Some(&"{\"query\":{}}".to_string()));
+    }
+
+    #[test]
+    fn test_stdin_args_from_empty_reader_has_no_body() {
+        let args = StdinArgs::from_reader(&mut "".as_bytes()).unwrap();
+        assert_eq!(args.body(), None);
+    }
+
     #[test]
     fn test_stdin_args_implements_http_request_args() {
         let args = StdinArgs {
             input: Some("test body".to_string()),
-            headers: HashMap::new(),
+            headers: RequestHeaders::new(),
         };
 
         // Test HttpRequestArgs implementation
@@ -136,7 +181,7 @@ mod tests {
     fn test_stdin_args_with_body() {
         let args = StdinArgs {
             input: Some("request body content".to_string()),
-            headers: HashMap::new(),
+            headers: RequestHeaders::new(),
         };
 
         assert_eq!(args.body(), Some(&"request body content".to_string()));
@@ -146,7 +191,7 @@ mod tests {
     fn test_stdin_args_without_body() {
         let args = StdinArgs {
             input: None,
-            headers: HashMap::new(),
+            headers: RequestHeaders::new(),
         };
 
         assert_eq!(args.body(), None);
@@ -156,7 +201,7 @@ mod tests {
     fn test_stdin_args_debug_and_clone() {
         let args = StdinArgs {
             input: Some("test".to_string()),
-            headers: HashMap::new(),
+            headers: RequestHeaders::new(),
         };
 
         // Test Debug trait
@@ -240,17 +285,17 @@ mod tests {
     fn test_stdin_args_equality() {
         let args1 = StdinArgs {
             input: Some("test".to_string()),
-            headers: HashMap::new(),
+            headers: RequestHeaders::new(),
         };
 
         let args2 = StdinArgs {
             input: Some("test".to_string()),
-            headers: HashMap::new(),
+            headers: RequestHeaders::new(),
         };
 
         let args3 = StdinArgs {
             input: Some("different".to_string()),
-            headers: HashMap::new(),
+            headers: RequestHeaders::new(),
         };
 
         assert_eq!(args1, args2);
@@ -259,7 +304,7 @@ mod tests {
 
     #[test]
     fn test_stdin_args_with_headers() {
-        let mut headers = HashMap::new();
+        let mut headers = RequestHeaders::new();
         headers.insert("content-type".to_string(), "application/json".to_string());
 
         let args = StdinArgs {
@@ -0,0 +1,141 @@
+use anyhow::Context;
+use regex::{Captures, Regex};
+
+use crate::utils::Result;
+
+// `${NAME}` like a shell, or `{{name}}` like most template engines
+const PLACEHOLDER: &str =
+    r"\$\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}|\{\{\s*([A-Za-z_][A-Za-z0-9_.-]*)\s*\}\}";
+
+/// A `--var NAME=VALUE` definition
+pub type Var = (String, String);
+
+/// Parses a `--var NAME=VALUE` definition. The value may be empty.
+pub fn parse_var(s: &str) -> Result<Var> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(anyhow::anyhow!(
+            "Invalid variable '{s}', expected NAME=VALUE, e.g. user_id=42"
+        )),
+    }
+}
+
+/// Reads the body template at `path` and substitutes its placeholders
+/// with `vars`, the last definition of a name winning, else with the
+/// environment variables.
+pub fn render_body_template(path: &str, vars: &[Var]) -> Result<String> {
+    let path = shellexpand::tilde(path).to_string();
+    let template = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read body template '{path}'"))?;
+    substitute(&template, |name| {
+        vars.iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| std::env::var_os(name)?.into_string().ok())
+    })
+    .with_context(|| format!("Failed to render body template '{path}'"))
+}
+
+/// Replaces the placeholders of `template` with the value `lookup`
+/// gives for their name. Every undefined name is reported at once.
+fn substitute(template: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut undefined = vec![];
+    let placeholder = Regex::new(PLACEHOLDER).unwrap();
+    let rendered = placeholder.replace_all(template, |caps: &Captures| {
+        let name = caps.get(1).or(caps.get(2)).unwrap().as_str();
+        lookup(name).unwrap_or_else(|| {
+            if !undefined.iter().any(|n| n == name) {
+                undefined.push(name.to_string());
+            }
+            String::new()
+        })
+    });
+    if !undefined.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Undefined variables: {}. Define them with --var NAME=VALUE or in the environment",
+            undefined.join(", ")
+        ));
+    }
+    Ok(rendered.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "USER_ID" => Some("42".to_string()),
+            "team.name" => Some("core".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_substitute() {
+        assert_eq!(
+            substitute(
+                r#"{"id": ${USER_ID}, "team": "{{ team.name }}", "note": "${ EMPTY }"}"#,
+                lookup
+            )
+            .unwrap(),
+            r#"{"id": 42, "team": "core", "note": ""}"#
+        );
+        // Not placeholders
+        assert_eq!(
+            substitute(r#"{"a": {"b": 1}} $HOME ${1} {{}}"#, lookup).unwrap(),
+            r#"{"a": {"b": 1}} $HOME ${1} {{}}"#
+        );
+    }
+
+    #[test]
+    fn test_substitute_undefined() {
+        let err = substitute("${A} {{b}} ${USER_ID} ${A}", lookup).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Undefined variables: A, b. Define them with --var NAME=VALUE or in the environment"
+        );
+    }
+
+    #[test]
+    fn test_parse_var() {
+        assert_eq!(
+            parse_var("user_id=42").unwrap(),
+            ("user_id".to_string(), "42".to_string())
+        );
+        assert_eq!(
+            parse_var("q=a=b").unwrap(),
+            ("q".to_string(), "a=b".to_string())
+        );
+        assert_eq!(parse_var("empty=").unwrap().1, "");
+        assert!(parse_var("user_id").is_err());
+        assert!(parse_var("=42").is_err());
+    }
+
+    #[test]
+    fn test_render_body_template() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"{\"id\": ${HTTPC_TEST_TEMPLATE_ID}, \"name\": \"{{name}}\"}")
+            .unwrap();
+        let path = file.path().to_str().unwrap();
+        let vars = [
+            ("name".to_string(), "a".to_string()),
+            ("name".to_string(), "b".to_string()),
+            ("HTTPC_TEST_TEMPLATE_ID".to_string(), "7".to_string()),
+        ];
+        assert_eq!(
+            render_body_template(path, &vars).unwrap(),
+            "{\"id\": 7, \"name\": \"b\"}"
+        );
+
+        let err = render_body_template(path, &vars[..1]).unwrap_err();
+        assert!(format!("{err:#}").contains("Undefined variables: HTTPC_TEST_TEMPLATE_ID"));
+        assert!(render_body_template("/nonexistent/body.json", &vars).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Time spent in each stage of a request. DNS and connect are zero
+/// when the connection of a previous request was reused.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    pub dns: Duration,
+    pub connect: Duration,
+    /// Until the response headers are received
+    pub ttfb: Duration,
+    /// Until the response body has been read
+    pub total: Duration,
+}
+
+impl Timings {
+    /// Formats the timings like the value of a `Server-Timing` header,
+    /// in milliseconds.
+    /// See https://www.w3.org/TR/server-timing/
+    pub fn to_server_timing(self) -> String {
+        [
+            ("dns", self.dns),
+            ("connect", self.connect),
+            ("ttfb", self.ttfb),
+            ("total", self.total),
+        ]
+        .iter()
+        .map(|(name, d)| format!("{name};dur={:.3}", d.as_secs_f64() * 1000.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// Collects the DNS and connect times of the connections opened by a
+/// client, which happen deep in the HTTP client.
+#[derive(Debug, Clone, Default)]
+pub struct StageClock {
+    stages: Arc<Mutex<Stages>>,
+}
+
+#[derive(Debug, Default)]
+struct Stages {
+    dns: Duration,
+    connect: Duration,
+}
+
+impl StageClock {
+    pub fn reset(&self) {
+        *self.stages.lock().unwrap() = Default::default();
+    }
+
+    /// The DNS and connect times since the last `reset`. The connect
+    /// time does not include the DNS lookup made while connecting.
+    pub fn stages(&self) -> (Duration, Duration) {
+        let stages = self.stages.lock().unwrap();
+        (stages.dns, stages.connect.saturating_sub(stages.dns))
+    }
+
+    pub fn add_dns(&self, elapsed: Duration) {
+        self.stages.lock().unwrap().dns += elapsed;
+    }
+
+    fn add_connect(&self, elapsed: Duration) {
+        self.stages.lock().unwrap().connect += elapsed;
+    }
+}
+
+/// Resolves the host names with the system resolver like the default
+/// one of the HTTP client, timing each lookup.
+pub struct TimedResolver(pub StageClock);
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let clock = self.0.clone();
+        Box::pin(async move {
+            let started = Instant::now();
+            let addrs = tokio::net::lookup_host((name.as_str(), 0)).await;
+            clock.add_dns(started.elapsed());
+            let addrs: Addrs = Box::new(addrs?.collect::<Vec<SocketAddr>>().into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Times the connections opened by the HTTP client, from the DNS
+/// lookup until the TLS handshake is done.
+#[derive(Clone)]
+pub struct TimedConnectLayer(pub StageClock);
+
+impl<S> Layer<S> for TimedConnectLayer {
+    type Service = TimedConnect<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimedConnect {
+            inner,
+            clock: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TimedConnect<S> {
+    inner: S,
+    clock: StageClock,
+}
+
+type BoxFuture<T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send>>;
+
+impl<S, R> Service<R> for TimedConnect<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<S::Response, S::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let clock = self.clock.clone();
+        let connecting = self.inner.call(req);
+        Box::pin(async move {
+            let started = Instant::now();
+            let conn = connecting.await;
+            clock.add_connect(started.elapsed());
+            conn
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_server_timing() {
+        let timings = Timings {
+            dns: Duration::from_micros(1500),
+            connect: Duration::ZERO,
+            ttfb: Duration::from_millis(42),
+            total: Duration::from_secs(1),
+        };
+        assert_eq!(
+            timings.to_server_timing(),
+            "dns;dur=1.500, connect;dur=0.000, ttfb;dur=42.000, total;dur=1000.000"
+        );
+    }
+
+    #[test]
+    fn test_stage_clock() {
+        let clock = StageClock::default();
+        clock.add_dns(Duration::from_millis(5));
+        clock.add_connect(Duration::from_millis(20));
+        assert_eq!(
+            clock.stages(),
+            (Duration::from_millis(5), Duration::from_millis(15))
+        );
+
+        clock.reset();
+        assert_eq!(clock.stages(), (Duration::ZERO, Duration::ZERO));
+    }
+}
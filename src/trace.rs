@@ -0,0 +1,247 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use anyhow::Context;
+use reqwest::header::{HeaderMap, HOST};
+use reqwest::Request;
+
+use crate::http::HttpResponse;
+use crate::utils::{is_secret_header, Result, REDACTED};
+
+/// `--trace-ascii -` writes the trace to stdout, like curl.
+pub const TRACE_STDOUT: &str = "-";
+
+/// Dump of the requests and responses in a readable form, with `=>`
+/// for what is sent and `<=` for what is received.
+pub struct WireTrace {
+    target: String,
+    redact: bool,
+}
+
+impl WireTrace {
+    /// Truncates the trace file so that it only holds this run. The
+    /// requests are then appended to it one by one.
+    pub fn new(target: &str, redact: bool) -> Result<Self> {
+        if target != TRACE_STDOUT {
+            std::fs::File::create(target)
+                .with_context(|| format!("Failed to create trace file '{target}'"))?;
+        }
+        Ok(Self {
+            target: target.to_string(),
+            redact,
+        })
+    }
+
+    /// Traces `req` with the client's default headers it will be sent
+    /// with. `Host` and `Content-Length` are added the way the HTTP
+    /// client does it.
+    pub fn request(&self, req: &Request, default_headers: &HeaderMap) -> Result<()> {
+        self.write(&format_request(req, default_headers, self.redact))
+    }
+
+    pub fn response(&self, res: &HttpResponse) -> Result<()> {
+        self.write(&format_response(res))
+    }
+
+    fn write(&self, text: &str) -> Result<()> {
+        if self.target == TRACE_STDOUT {
+            std::io::stdout().write_all(text.as_bytes())?;
+            return Ok(());
+        }
+        OpenOptions::new()
+            .append(true)
+            .open(&self.target)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .with_context(|| format!("Failed to write trace file '{}'", self.target))
+    }
+}
+
+fn format_request(req: &Request, default_headers: &HeaderMap, redact: bool) -> String {
+    let lines = request_head(req, default_headers, redact);
+    let body = request_body(req);
+    let mut out = section("=> Send header", &lines);
+    if !body.is_empty() {
+        out.push_str(&data("=> Send data", body));
+    }
+    out
+}
+
+/// The request line and headers of `req` the way they are sent, the
+/// `default_headers` of the client included.
+pub fn request_head(req: &Request, default_headers: &HeaderMap, redact: bool) -> Vec<String> {
+    let url = req.url();
+    let mut target = url.path().to_string();
+    if let Some(query) = url.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+
+    let mut lines = vec![format!("{} {target} {:?}", req.method(), req.version())];
+    // Unless overridden, e.g. with --host-header
+    if let Some(host) = url.host_str().filter(|_| !req.headers().contains_key(HOST)) {
+        match url.port() {
+            Some(port) => lines.push(format!("host: {host}:{port}")),
+            None => lines.push(format!("host: {host}")),
+        }
+    }
+
+    let body = request_body(req);
+    let defaults = default_headers
+        .iter()
+        .filter(|(name, _)| !req.headers().contains_key(*name));
+    for (name, value) in req.headers().iter().chain(defaults) {
+        let value = if redact && is_secret_header(name.as_str()) {
+            REDACTED.to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).to_string()
+        };
+        lines.push(format!("{name}: {value}"));
+    }
+    if !body.is_empty() {
+        lines.push(format!("content-length: {}", body.len()));
+    }
+    lines
+}
+
+pub fn request_body(req: &Request) -> &[u8] {
+    req.body().and_then(|b| b.as_bytes()).unwrap_or_default()
+}
+
+/// The status and headers of `res`.
+pub fn response_head(res: &HttpResponse) -> Vec<String> {
+    let mut lines = vec![res.status().to_string()];
+    for (name, value) in res.headers() {
+        lines.push(format!(
+            "{name}: {}",
+            String::from_utf8_lossy(value.as_bytes())
+        ));
+    }
+    lines
+}
+
+fn format_response(res: &HttpResponse) -> String {
+    let mut out = section("<= Recv header", &response_head(res));
+    if !res.bytes().is_empty() {
+        out.push_str(&data("<= Recv data", res.bytes()));
+    }
+    out
+}
+
+fn section(title: &str, lines: &[String]) -> String {
+    let mut out = format!("{title}\n");
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+// Bodies are printed as text. Bytes that are not valid UTF-8 (e.g. a
+// compressed body) are shown as '.' like curl does.
+fn data(title: &str, bytes: &[u8]) -> String {
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' || b == b'\n' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect(),
+    };
+    let mut out = format!("{title}, {} bytes\n{text}", bytes.len());
+    if !text.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use reqwest::Client;
+
+    fn test_request() -> Request {
+        Client::new()
+            .post("http://localhost:8080/api/items?page=2")
+            .header("content-type", "application/json")
+            .header("x-trace", "1")
+            .header("x-trace", "2")
+            .basic_auth("admin", Some("secret"))
+            .header("cookie", "session=abc")
+            .body("{\"a\":1}")
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_format_request() {
+        let mut defaults = HeaderMap::new();
+        defaults.insert("user-agent", HeaderValue::from_static("httpc-test"));
+        defaults.insert("x-trace", HeaderValue::from_static("default"));
+
+        let trace = format_request(&test_request(), &defaults, true);
+        assert_eq!(
+            trace,
+            "=> Send header\n\
+             POST /api/items?page=2 HTTP/1.1\n\
+             host: localhost:8080\n\
+             content-type: application/json\n\
+             x-trace: 1\n\
+             x-trace: 2\n\
+             authorization: <redacted>\n\
+             cookie: <redacted>\n\
+             user-agent: httpc-test\n\
+             content-length: 7\n\
+             => Send data, 7 bytes\n\
+             {\"a\":1}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_request_without_redaction() {
+        let trace = format_request(&test_request(), &HeaderMap::new(), false);
+        // base64("admin:secret")
+        assert!(trace.contains("authorization: Basic YWRtaW46c2VjcmV0\n"));
+        assert!(trace.contains("cookie: session=abc\n"));
+    }
+
+    #[test]
+    fn test_request_head_with_host_header() {
+        let req = Client::new()
+            .get("http://10.0.0.5/")
+            .header("host", "shop.example.com")
+            .build()
+            .unwrap();
+        assert_eq!(
+            request_head(&req, &HeaderMap::new(), true),
+            vec!["GET / HTTP/1.1", "host: shop.example.com"]
+        );
+    }
+
+    #[test]
+    fn test_data_with_binary_body() {
+        assert_eq!(
+            data("<= Recv data", &[0x1f, 0x8b, b'a', b' ', 0xff]),
+            "<= Recv data, 5 bytes\n..a .\n"
+        );
+    }
+
+    #[test]
+    fn test_write_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trace.txt");
+        std::fs::write(&path, "previous run\n").unwrap();
+
+        let trace = WireTrace::new(path.to_str().unwrap(), true).unwrap();
+        trace.write("first\n").unwrap();
+        trace.write("second\n").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+        assert!(WireTrace::new("/nonexistent/dir/trace.txt", true).is_err());
+    }
+}
@@ -1,5 +1,30 @@
+use anyhow::Context;
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
 pub type Result<T> = anyhow::Result<T>;
 
+/// Printed in place of a password or a credential header.
+pub const REDACTED: &str = "<redacted>";
+
+// Headers whose value is a credential
+const SECRET_HEADERS: [&str; 3] = ["authorization", "proxy-authorization", "cookie"];
+
+/// Whether the value of the header `name` is a credential, which is
+/// redacted when printed.
+pub fn is_secret_header(name: &str) -> bool {
+    SECRET_HEADERS
+        .iter()
+        .any(|secret| name.eq_ignore_ascii_case(secret))
+}
+
 pub type Merger<T> = fn(T, T) -> T;
 
 #[allow(dead_code)]
@@ -12,6 +37,229 @@ pub fn merge_opt<T>(o1: Option<T>, o2: Option<T>, merger: Merger<T>) -> Option<T
     }
 }
 
+/// Parses a number of seconds (fractions allowed, e.g. `2.5`) into a Duration.
+pub fn parse_seconds(s: &str) -> Result<Duration> {
+    let secs = s
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| anyhow::anyhow!("Invalid number of seconds '{s}'"))?;
+    Duration::try_from_secs_f64(secs)
+        .ok()
+        .filter(|d| !d.is_zero())
+        .ok_or_else(|| anyhow::anyhow!("Number of seconds must be greater than 0: '{s}'"))
+}
+
+/// Parses a size in bytes, optionally with a `K` or `M` suffix for
+/// KiB and MiB (e.g. `8192`, `64K`, `1M`).
+pub fn parse_size(s: &str) -> Result<usize> {
+    let invalid =
+        || anyhow::anyhow!("Invalid size '{s}', expected a number of bytes, e.g. 8192, 64K or 1M");
+    let trimmed = s.trim();
+    let (number, unit) = match trimmed.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&trimmed[..i], 1024),
+        Some((i, 'm' | 'M')) => (&trimmed[..i], 1024 * 1024),
+        _ => (trimmed, 1),
+    };
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .filter(|&n| n > 0)
+        .ok_or_else(invalid)
+}
+
+/// Decodes standard base64 (with padding) into bytes, ignoring the
+/// line breaks of wrapped output such as the one of `base64`.
+pub fn parse_base64(s: &str) -> Result<Bytes> {
+    let compact: String = s.split_whitespace().collect();
+    STANDARD
+        .decode(compact)
+        .map(Bytes::from)
+        .with_context(|| format!("Invalid base64 data '{s}'"))
+}
+
+/// Decodes a response body holding base64 data, either bare (wrapped
+/// over several lines or not), as a JSON string or as a `data:` URL.
+/// The URL-safe alphabet and missing padding are accepted too.
+pub fn decode_base64_body(body: &[u8]) -> Result<Bytes> {
+    let invalid = || anyhow::anyhow!("Response body is not valid base64");
+    let text = std::str::from_utf8(body).map_err(|_| invalid())?.trim();
+    let text = serde_json::from_str::<String>(text).unwrap_or_else(|_| text.to_string());
+    // data:[<media type>][;base64],<data>
+    let data = match text.strip_prefix("data:") {
+        Some(url) => match url.split_once(',') {
+            Some((meta, data)) if meta.ends_with(";base64") => data,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Response body is a data: URL without base64 data"
+                ))
+            }
+        },
+        None => &text,
+    };
+    let compact: String = data.split_whitespace().collect();
+    STANDARD
+        .decode(&compact)
+        .or_else(|_| URL_SAFE_NO_PAD.decode(compact.trim_end_matches('=')))
+        .map(Bytes::from)
+        .map_err(|_| invalid())
+}
+
+/// Parses a byte range in `start-end`, `start-` or `-suffix` form into
+/// the value of a `Range` header (e.g. `bytes=0-1023`).
+pub fn parse_byte_range(s: &str) -> Result<String> {
+    let invalid = || {
+        anyhow::anyhow!("Invalid byte range '{s}', expected <start>-<end>, <start>- or -<suffix>")
+    };
+    let (start, end) = s.trim().split_once('-').ok_or_else(invalid)?;
+    let parse = |v: &str| {
+        (!v.is_empty())
+            .then(|| v.parse::<u64>().map_err(|_| invalid()))
+            .transpose()
+    };
+
+    match (parse(start)?, parse(end)?) {
+        (None, None) => Err(invalid()),
+        (Some(first), Some(last)) if first > last => Err(anyhow::anyhow!(
+            "Invalid byte range '{s}', start must not be greater than end"
+        )),
+        _ => Ok(format!("bytes={start}-{end}")),
+    }
+}
+
+/// Runs `command` with the shell, feeding `input` to its stdin, and
+/// returns what it wrote to stdout. Its stderr goes to ours.
+pub fn pipe_through(command: &str, input: &[u8]) -> Result<Vec<u8>> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("Failed to run pipe command '{command}'"))?;
+
+    // Write from another thread so that a command producing a lot of
+    // output before reading all of its input can't deadlock us
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to run pipe command '{command}'"))?;
+    match writer.join().unwrap() {
+        // The command may exit without reading everything (e.g. head)
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+            return Err(e).with_context(|| format!("Failed to write to pipe command '{command}'"))
+        }
+        _ => {}
+    }
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Pipe command '{command}' failed with {}",
+            output.status
+        ));
+    }
+    Ok(output.stdout)
+}
+
+const CONTENT_TYPE_DEFAULT: &str = "application/octet-stream";
+
+/// The content type of a file, guessed from the common extensions. The
+/// others are application/octet-stream.
+pub fn content_type_for_path(path: &str) -> &'static str {
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "yaml" | "yml" => "application/yaml",
+        "ndjson" => "application/x-ndjson",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" => "text/javascript",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "mp4" => "video/mp4",
+        _ => CONTENT_TYPE_DEFAULT,
+    }
+}
+
+/// Renders headers as `name: value` lines sorted by name, with the names
+/// right-aligned so that the colons line up. When `numbered`, the names
+/// of the repeated headers are suffixed with their index in the order
+/// received, e.g. `set-cookie[0]`.
+pub fn sorted_header_lines(headers: &HeaderMap, numbered: bool) -> Vec<String> {
+    let mut entries = header_entries(headers, numbered);
+    // Stable sort keeps repeated headers in the order they were received
+    // and the index is not part of the name, `[10]` sorting before `[2]`
+    entries.sort_by(|a, b| base_name(&a.0).cmp(base_name(&b.0)));
+
+    let width = entries
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    entries
+        .into_iter()
+        .map(|(name, value)| format!("{name:>width$}: {value}"))
+        .collect()
+}
+
+/// Renders headers as `name: value` lines in the order they were
+/// received. See [`sorted_header_lines`] for `numbered`.
+pub fn header_lines(headers: &HeaderMap, numbered: bool) -> Vec<String> {
+    header_entries(headers, numbered)
+        .into_iter()
+        .map(|(name, value)| format!("{name}: {value}"))
+        .collect()
+}
+
+// The name without the index `header_entries` may have suffixed
+fn base_name(name: &str) -> &str {
+    name.split('[').next().unwrap_or(name)
+}
+
+// The name and value of a header
+type HeaderEntry = (String, String);
+
+// The name and value of each header. The names of the repeated ones
+// are suffixed with their index when `numbered`.
+fn header_entries(headers: &HeaderMap, numbered: bool) -> Vec<HeaderEntry> {
+    headers
+        .keys()
+        .flat_map(|name| {
+            let values = headers.get_all(name).iter().collect::<Vec<_>>();
+            let repeated = numbered && values.len() > 1;
+            values.into_iter().enumerate().map(move |(i, value)| {
+                let name = match repeated {
+                    true => format!("{name}[{i}]"),
+                    false => name.to_string(),
+                };
+                (name, String::from_utf8_lossy(value.as_bytes()).into_owned())
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -30,4 +278,186 @@ mod test {
     fn merge_opt_should_return_merged_value_when_both_are_not_none() {
         assert_eq!(merge_opt(Some(1), Some(2), |a, b| a + b), Some(3));
     }
+
+    #[test]
+    fn is_secret_header_should_match_credential_headers() {
+        assert!(is_secret_header("Authorization"));
+        assert!(is_secret_header("proxy-authorization"));
+        assert!(is_secret_header("COOKIE"));
+        assert!(!is_secret_header("set-cookie"));
+        assert!(!is_secret_header("x-api-version"));
+    }
+
+    #[test]
+    fn content_type_for_path_should_guess_from_extension() {
+        assert_eq!(content_type_for_path("data/users.json"), "application/json");
+        assert_eq!(content_type_for_path("Photo.JPG"), "image/jpeg");
+        assert_eq!(content_type_for_path("dump.tar.gz"), "application/gzip");
+        assert_eq!(content_type_for_path("disk.img"), CONTENT_TYPE_DEFAULT);
+        assert_eq!(content_type_for_path("Makefile"), CONTENT_TYPE_DEFAULT);
+    }
+
+    #[test]
+    fn parse_byte_range_should_accept_all_forms() {
+        assert_eq!(parse_byte_range("0-1023").unwrap(), "bytes=0-1023");
+        assert_eq!(parse_byte_range("5-5").unwrap(), "bytes=5-5");
+        assert_eq!(parse_byte_range("1024-").unwrap(), "bytes=1024-");
+        assert_eq!(parse_byte_range("-500").unwrap(), "bytes=-500");
+    }
+
+    #[test]
+    fn parse_byte_range_should_reject_invalid_values() {
+        for input in ["", "-", "100", "a-b", "10-5", "1-2-3", "-1-", "1.5-2"] {
+            assert!(
+                parse_byte_range(input).is_err(),
+                "Expected error for: {input}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_size_should_accept_suffixes() {
+        assert_eq!(parse_size("8192").unwrap(), 8192);
+        assert_eq!(parse_size("64K").unwrap(), 64 * 1024);
+        assert_eq!(parse_size(" 1m ").unwrap(), 1024 * 1024);
+        for input in ["", "0", "K", "-1", "1.5K", "1G"] {
+            assert!(parse_size(input).is_err(), "Expected error for: {input}");
+        }
+    }
+
+    #[test]
+    fn parse_base64_should_decode_binary_data() {
+        assert_eq!(parse_base64("AAH/").unwrap().as_ref(), [0x00, 0x01, 0xff]);
+        assert_eq!(parse_base64("aGVs\nbG8=\n").unwrap().as_ref(), b"hello");
+        assert!(parse_base64("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn decode_base64_body_should_accept_the_usual_forms() {
+        for body in [
+            "aGVs\nbG8=\n",
+            "\"aGVsbG8=\"",
+            "aGVsbG8",
+            "data:text/plain;base64,aGVsbG8=",
+        ] {
+            assert_eq!(
+                decode_base64_body(body.as_bytes()).unwrap().as_ref(),
+                b"hello",
+                "Failed to decode: {body}"
+            );
+        }
+        // "\xfb\xff" is "+/8=" in the standard alphabet
+        assert_eq!(decode_base64_body(b"-_8").unwrap().as_ref(), [0xfb, 0xff]);
+    }
+
+    #[test]
+    fn decode_base64_body_should_reject_invalid_data() {
+        for body in [&b"not base64!"[..], b"{\"data\": 1}", &[0xff, 0xfe]] {
+            assert_eq!(
+                decode_base64_body(body).unwrap_err().to_string(),
+                "Response body is not valid base64"
+            );
+        }
+        assert_eq!(
+            decode_base64_body(b"data:text/plain,hello")
+                .unwrap_err()
+                .to_string(),
+            "Response body is a data: URL without base64 data"
+        );
+    }
+
+    #[test]
+    fn parse_base64_should_reject_invalid_data() {
+        for input in ["not base64!", "aGVsbG8", "a"] {
+            assert!(parse_base64(input).is_err(), "Expected error for: {input}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pipe_through_should_return_command_output() {
+        let output = pipe_through("tr a-z A-Z", b"hello").unwrap();
+        assert_eq!(output, b"HELLO");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pipe_through_should_ignore_unread_input() {
+        let input = "line\n".repeat(100_000);
+        let output = pipe_through("head -n 1", input.as_bytes()).unwrap();
+        assert_eq!(output, b"line\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pipe_through_should_fail_when_command_fails() {
+        let err = pipe_through("cat > /dev/null; exit 3", b"x").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Pipe command 'cat > /dev/null; exit 3' failed"));
+    }
+
+    #[test]
+    fn sorted_header_lines_should_sort_and_align() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "abc".parse().unwrap());
+        headers.insert("date", "today".parse().unwrap());
+        headers.append("set-cookie", "b=2".parse().unwrap());
+        headers.append("set-cookie", "a=1".parse().unwrap());
+        headers.insert("content-type", "text/plain".parse().unwrap());
+
+        assert_eq!(
+            sorted_header_lines(&headers, false),
+            vec![
+                "content-type: text/plain",
+                "        date: today",
+                "  set-cookie: b=2",
+                "  set-cookie: a=1",
+                "x-request-id: abc",
+            ]
+        );
+    }
+
+    #[test]
+    fn header_lines_should_number_repeated_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append("set-cookie", "b=2".parse().unwrap());
+        headers.insert("date", "today".parse().unwrap());
+        headers.append("set-cookie", "a=1".parse().unwrap());
+
+        assert_eq!(
+            header_lines(&headers, true),
+            vec!["set-cookie[0]: b=2", "set-cookie[1]: a=1", "date: today"]
+        );
+        assert_eq!(
+            header_lines(&headers, false),
+            vec!["set-cookie: b=2", "set-cookie: a=1", "date: today"]
+        );
+        assert_eq!(
+            sorted_header_lines(&headers, true),
+            vec![
+                "         date: today",
+                "set-cookie[0]: b=2",
+                "set-cookie[1]: a=1",
+            ]
+        );
+    }
+
+    #[test]
+    fn sorted_header_lines_should_handle_empty_headers() {
+        assert!(sorted_header_lines(&HeaderMap::new(), false).is_empty());
+    }
+
+    #[test]
+    fn parse_seconds_should_accept_integers_and_fractions() {
+        assert_eq!(parse_seconds("5").unwrap(), Duration::from_secs(5));
+        assert_eq!(parse_seconds(" 2.5 ").unwrap(), Duration::from_millis(2500));
+    }
+
+    #[test]
+    fn parse_seconds_should_reject_invalid_values() {
+        for input in ["", "abc", "0", "-1", "NaN", "inf"] {
+            assert!(parse_seconds(input).is_err(), "Expected error for: {input}");
+        }
+    }
 }
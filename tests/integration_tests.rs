@@ -30,6 +30,19 @@ fn test_version_command() {
     assert!(stdout.contains("httpc"));
 }
 
+#[test]
+fn test_completions_command() {
+    let output = Command::new(httpc_binary())
+        .args(["--completions", "bash"])
+        .output()
+        .expect("Failed to execute httpc");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("httpc"));
+    assert!(stdout.contains("--profile"));
+}
+
 #[test]
 fn test_basic_get_request() {
     let output = Command::new(httpc_binary())
@@ -476,3 +489,642 @@ fn test_case_insensitive_method() {
         }
     }
 }
+
+#[test]
+fn test_json_error_format_for_malformed_header() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let output = Command::new(httpc_binary())
+        .args([
+            "GET",
+            "http://127.0.0.1:1/path",
+            "-H",
+            "Bad Header: value",
+            "--error-format",
+            "json",
+        ])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let report: serde_json::Value =
+        serde_json::from_str(stderr.trim()).expect("stderr should be a JSON object");
+    assert_eq!(report["kind"], "request");
+    assert_eq!(report["url"], "http://127.0.0.1:1/path");
+    assert!(report["status"].is_null());
+    assert!(report["error"].as_str().unwrap().contains("bad header"));
+}
+
+#[test]
+fn test_json_error_format_for_header_without_colon() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let output = Command::new(httpc_binary())
+        .args([
+            "--error-format",
+            "json",
+            "GET",
+            "http://127.0.0.1:1/x",
+            "-H",
+            "bogus",
+        ])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let report: serde_json::Value =
+        serde_json::from_str(stderr.trim()).expect("stderr should be a JSON object");
+    assert_eq!(report["kind"], "request");
+    assert_eq!(report["url"], "http://127.0.0.1:1/x");
+    assert_eq!(
+        report["error"],
+        "Invalid header format 'bogus', expected 'Name: value'"
+    );
+}
+
+#[test]
+fn test_har_list_entries() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let har_path = temp_dir.path().join("session.har");
+    std::fs::write(
+        &har_path,
+        r#"{"log": {"entries": [
+            {"request": {"method": "GET", "url": "https://api.example.com/items", "headers": []}},
+            {"request": {"method": "POST", "url": "http://localhost:8080/items?dry=1", "headers": []}}
+        ]}}"#,
+    )
+    .expect("Failed to write HAR file");
+
+    let output = Command::new(httpc_binary())
+        .args(["--har", har_path.to_str().unwrap(), "--list"])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "0: GET https://api.example.com/items\n1: POST http://localhost:8080/items?dry=1\n"
+    );
+}
+
+#[test]
+fn test_range_partial_content() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    // A one-shot server answering 206 with the requested slice
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut range = None;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if let Some(value) = line.to_lowercase().strip_prefix("range:") {
+                range = Some(value.trim().to_string());
+            }
+            if line == "\r\n" {
+                break;
+            }
+        }
+        reader
+            .get_mut()
+            .write_all(
+                b"HTTP/1.1 206 Partial Content\r\n\
+                  content-range: bytes 2-5/10\r\n\
+                  content-length: 4\r\n\r\n\
+                  2345",
+            )
+            .unwrap();
+        range
+    });
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let output = Command::new(httpc_binary())
+        .args([
+            "GET",
+            &format!("http://127.0.0.1:{port}/file.bin"),
+            "--range",
+            "2-5",
+        ])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+
+    assert_eq!(server.join().unwrap(), Some("bytes=2-5".to_string()));
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2345\n");
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_paginate_merge_pages() {
+    use std::io::{BufRead, BufReader, Write};
+
+    // Three pages linking to the next one, each on a new connection
+    let (listener, port) = local_listener();
+    let server = std::thread::spawn(move || {
+        let pages = [
+            ("<?page=2>; rel=\"next\"", "[1, 2]"),
+            ("<?page=3>; rel=\"next\", <?page=1>; rel=\"prev\"", "[3]"),
+            ("<?page=2>; rel=\"prev\"", "[4]"),
+        ];
+        let mut targets = vec![];
+        for (link, body) in pages {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            targets.push(request_line.split(' ').nth(1).unwrap().to_string());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\nlink: {link}\r\n\
+                 content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+        }
+        targets
+    });
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let output = Command::new(httpc_binary())
+        .args([
+            "GET",
+            &format!("http://127.0.0.1:{port}/items"),
+            "--paginate",
+            "--merge-pages",
+            "--pretty",
+            "none",
+        ])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+
+    assert_eq!(
+        server.join().unwrap(),
+        vec!["/items", "/items?page=2", "/items?page=3"]
+    );
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "[1,2,3,4]\n");
+}
+
+#[test]
+fn test_decode_base64_body() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let run = |body: &'static str| {
+        let (listener, port) = local_listener();
+        let server = serve_once(listener, "200 OK", body);
+        let output = Command::new(httpc_binary())
+            .args([
+                "GET",
+                &format!("http://127.0.0.1:{port}/blob"),
+                "--decode-base64-body",
+            ])
+            .env("HOME", temp_dir.path())
+            .output()
+            .expect("Failed to execute httpc");
+        server.join().unwrap();
+        output
+    };
+
+    let output = run("aGVsbG8gd29y\nbGQ=\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello world\n");
+
+    let output = run("not base64!");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Response body is not valid base64"));
+}
+
+/// Answers a single request on the listener with the given status line
+/// and plain text body.
+fn serve_once(
+    listener: std::net::TcpListener,
+    status: &'static str,
+    body: &'static str,
+) -> std::thread::JoinHandle<()> {
+    use std::io::{BufRead, BufReader, Write};
+
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let response = format!(
+            "HTTP/1.1 {status}\r\ncontent-type: text/plain\r\ncontent-length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        reader.get_mut().write_all(response.as_bytes()).unwrap();
+    })
+}
+
+fn local_listener() -> (std::net::TcpListener, u16) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Failed to bind");
+    let port = listener.local_addr().unwrap().port();
+    (listener, port)
+}
+
+#[test]
+fn test_multiple_urls() {
+    let (ok_listener, ok_port) = local_listener();
+    let (missing_listener, missing_port) = local_listener();
+    let ok_server = serve_once(ok_listener, "200 OK", "one");
+    let missing_server = serve_once(missing_listener, "404 Not Found", "nope");
+    let first = format!("http://127.0.0.1:{ok_port}/a");
+    let second = format!("http://127.0.0.1:{missing_port}/b");
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let output = Command::new(httpc_binary())
+        .args(["GET", &first, "--url", &second])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+    ok_server.join().unwrap();
+    missing_server.join().unwrap();
+
+    // The worst status decides the exit code
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        format!("==> {first} <==\none\n\n==> {second} <==\n")
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("404 Not Found: nope"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pipe_response_body() {
+    let (listener, port) = local_listener();
+    let server = serve_once(listener, "200 OK", "hello pipe");
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let output = Command::new(httpc_binary())
+        .args([
+            "GET",
+            &format!("http://127.0.0.1:{port}/"),
+            "--pipe",
+            "tr a-z A-Z",
+        ])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+    server.join().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "HELLO PIPE");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_pipe_command_failure() {
+    let (listener, port) = local_listener();
+    let server = serve_once(listener, "200 OK", "hello");
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let output = Command::new(httpc_binary())
+        .args([
+            "GET",
+            &format!("http://127.0.0.1:{port}/"),
+            "--pipe",
+            "cat > /dev/null; exit 4",
+        ])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+    server.join().unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Pipe command 'cat > /dev/null; exit 4' failed"));
+}
+
+#[test]
+fn test_trace_ascii_to_file() {
+    let (listener, port) = local_listener();
+    let server = serve_once(listener, "200 OK", "hello");
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let trace_path = temp_dir.path().join("trace.txt");
+    let output = Command::new(httpc_binary())
+        .args([
+            "GET",
+            &format!("http://127.0.0.1:{port}/traced?x=1"),
+            "-H",
+            "X-Request: 1",
+            "--user",
+            "admin",
+            "--password",
+            "secret",
+            "--trace-ascii",
+            trace_path.to_str().unwrap(),
+        ])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+    server.join().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+
+    let trace = std::fs::read_to_string(&trace_path).expect("Failed to read trace");
+    assert!(trace.starts_with(&format!(
+        "=> Send header\nGET /traced?x=1 HTTP/1.1\nhost: 127.0.0.1:{port}\n"
+    )));
+    assert!(trace.contains("x-request: 1\n"));
+    assert!(trace.contains("authorization: <redacted>\n"));
+    assert!(!trace.contains("=> Send data"));
+    assert!(trace.contains("<= Recv header\n200 OK\ncontent-type: text/plain\n"));
+    assert!(trace.ends_with("<= Recv data, 5 bytes\nhello\n"));
+}
+
+#[test]
+fn test_print_request_and_response_headers() {
+    let (listener, port) = local_listener();
+    let server = serve_once(listener, "200 OK", "hello");
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let output = Command::new(httpc_binary())
+        .args([
+            "GET",
+            &format!("http://127.0.0.1:{port}/printed"),
+            "-H",
+            "X-Request: 1",
+            "--print",
+            "Hhb",
+        ])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+    server.join().unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with(&format!(
+        "GET /printed HTTP/1.1\nhost: 127.0.0.1:{port}\nx-request: 1\n"
+    )));
+    assert!(stdout.contains("\n\n200 OK\ncontent-type: text/plain\n"));
+    assert!(stdout.ends_with("\n\nhello\n"));
+}
+
+#[test]
+fn test_netrc_credentials() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    std::fs::write(
+        temp_dir.path().join(".netrc"),
+        "machine 127.0.0.1 login admin password secret\n",
+    )
+    .expect("Failed to write .netrc");
+
+    let run = |extra: &[&str]| {
+        let (listener, port) = local_listener();
+        let server = serve_once(listener, "200 OK", "ok");
+        let url = format!("http://127.0.0.1:{port}/");
+        let mut args = vec!["GET", url.as_str(), "--print", "H"];
+        args.extend_from_slice(extra);
+        let output = Command::new(httpc_binary())
+            .args(&args)
+            .env("HOME", temp_dir.path())
+            .output()
+            .expect("Failed to execute httpc");
+        server.join().unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    // base64("admin:secret")
+    assert!(run(&[]).contains("authorization: Basic YWRtaW46c2VjcmV0\n"));
+    assert!(!run(&["--no-netrc"]).contains("authorization:"));
+    // Credentials on the command line win
+    assert!(!run(&["-u", "other", "-w", "pw"]).contains("YWRtaW46c2VjcmV0"));
+}
+
+#[test]
+fn test_pretty_json_with_wrong_content_type() {
+    let (listener, port) = local_listener();
+    let server = serve_once(listener, "200 OK", "{\"a\":[1,2]}");
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let output = Command::new(httpc_binary())
+        .args([
+            "GET",
+            &format!("http://127.0.0.1:{port}/"),
+            "--pretty",
+            "json",
+        ])
+        .env("HOME", temp_dir.path())
+        .output()
+        .expect("Failed to execute httpc");
+    server.join().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\n  \"a\": [\n    1,\n    2\n  ]\n}\n"
+    );
+}
+
+#[test]
+fn test_warn_body_size_without_terminal() {
+    use std::io::Write;
+
+    let (listener, port) = local_listener();
+    let server = serve_once(listener, "200 OK", "sent");
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let url = format!("http://127.0.0.1:{port}/");
+
+    let run = |extra: &[&str]| {
+        let mut cmd = Command::new(httpc_binary())
+            .args(["POST", &url, "--warn-body-size", "1K"])
+            .args(extra)
+            .env("HOME", temp_dir.path())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .expect("Failed to spawn httpc");
+        cmd.stdin.take().unwrap().write_all(&[b'x'; 2000]).unwrap();
+        cmd.wait_with_output().expect("Failed to read output")
+    };
+
+    // Refused before connecting, as there is no terminal to ask on
+    let output = run(&[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Request body is 2000 bytes, more than --warn-body-size 1024"));
+
+    let output = run(&["--yes"]);
+    server.join().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "sent\n");
+}
+
+#[test]
+fn test_warn_body_size_with_upload_file() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let upload = temp_dir.path().join("disk.img");
+    std::fs::write(&upload, [0u8; 2000]).unwrap();
+
+    // Refused before connecting, nothing listens on the port
+    let (listener, port) = local_listener();
+    drop(listener);
+    let output = Command::new(httpc_binary())
+        .args([
+            "PUT",
+            &format!("http://127.0.0.1:{port}/"),
+            "--upload-file",
+            upload.to_str().unwrap(),
+            "--warn-body-size",
+            "1K",
+        ])
+        .env("HOME", temp_dir.path())
+        .stdin(std::process::Stdio::null())
+        .output()
+        .expect("Failed to execute httpc");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Request body is 2000 bytes, more than --warn-body-size 1024"));
+}
+
+#[test]
+fn test_show_location() {
+    // The Location header rides along with the status line
+    let (listener, port) = local_listener();
+    let server = serve_once(
+        listener,
+        "302 Found\r\nlocation: https://example.com/target",
+        "moved",
+    );
+    let (missing_listener, missing_port) = local_listener();
+    let missing_server = serve_once(missing_listener, "302 Found", "moved");
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let run = |port: u16| {
+        Command::new(httpc_binary())
+            .args([
+                "GET",
+                &format!("http://127.0.0.1:{port}/s/abc"),
+                "--show-location",
+            ])
+            .env("HOME", temp_dir.path())
+            .output()
+            .expect("Failed to execute httpc")
+    };
+
+    let output = run(port);
+    server.join().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "https://example.com/target\n"
+    );
+
+    let output = run(missing_port);
+    missing_server.join().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("302 Found: the redirect has no Location header"));
+}
+
+#[test]
+fn test_body_template() {
+    let (listener, port) = local_listener();
+    let server = serve_once(listener, "201 Created", "");
+
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let template = temp_dir.path().join("user.json");
+    std::fs::write(&template, "{\"id\": ${USER_ID}, \"team\": \"{{team}}\"}").unwrap();
+    let output = Command::new(httpc_binary())
+        .args([
+            "POST",
+            &format!("http://127.0.0.1:{port}/users"),
+            "--body-template",
+            template.to_str().unwrap(),
+            "--var",
+            "team=core",
+            "--print",
+            "B",
+        ])
+        .env("HOME", temp_dir.path())
+        .env("USER_ID", "42")
+        .output()
+        .expect("Failed to execute httpc");
+    server.join().unwrap();
+
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).starts_with("{\"id\": 42, \"team\": \"core\"}\n")
+    );
+
+    // Nothing is sent with an undefined variable
+    let output = Command::new(httpc_binary())
+        .args([
+            "POST",
+            "http://127.0.0.1:9/users",
+            "--body-template",
+            template.to_str().unwrap(),
+        ])
+        .env("HOME", temp_dir.path())
+        .env_remove("USER_ID")
+        .output()
+        .expect("Failed to execute httpc");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Undefined variables: USER_ID, team"));
+}
+
+#[test]
+fn test_expect_body_file() {
+    let temp_dir = tempdir().expect("Failed to create temp dir");
+    let fixture = temp_dir.path().join("users.json");
+    std::fs::write(&fixture, "{\n  \"name\": \"alice\",\n  \"id\": 1\n}\n").unwrap();
+
+    let run = |body: &'static str| {
+        let (listener, port) = local_listener();
+        let server = serve_once(listener, "200 OK", body);
+        let output = Command::new(httpc_binary())
+            .args([
+                "GET",
+                &format!("http://127.0.0.1:{port}/users/1"),
+                "--expect-body-file",
+                fixture.to_str().unwrap(),
+            ])
+            .env("HOME", temp_dir.path())
+            .output()
+            .expect("Failed to execute httpc");
+        server.join().unwrap();
+        output
+    };
+
+    // Same JSON value, whatever the key order and whitespace
+    let output = run("{\"id\":1,\"name\":\"alice\"}");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "{\"id\":1,\"name\":\"alice\"}\n"
+    );
+
+    let output = run("{\"id\":1,\"name\":\"bob\"}");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("does not match"));
+    assert!(stderr.contains("-   \"name\": \"alice\"\n+   \"name\": \"bob\""));
+}